@@ -0,0 +1,131 @@
+//! Bearer-token authentication for the REST API: validates the
+//! `Authorization: Bearer <token>` header against hashed-at-rest tokens
+//! loaded from `[api] tokens` config, and gates destructive routes behind
+//! an admin-scoped token.
+//!
+//! `require_token` runs first (as a router-wide layer) and attaches the
+//! matched token's [`TokenScope`] to the request; `require_admin` runs
+//! second (as a per-route layer on destructive routes) and checks that
+//! scope. Splitting it this way means a handler never has to re-parse the
+//! header, and a route that forgets `require_admin` still rejects
+//! unauthenticated requests via `require_token`.
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::config::{ApiToken, TokenScope};
+use crate::AppState;
+
+#[derive(Clone, Copy)]
+struct AuthorizedScope(TokenScope);
+
+pub async fn require_token(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let Some(token) = bearer_token(&req) else {
+        return unauthorized("Missing or malformed Authorization header");
+    };
+
+    let Some(scope) = resolve_scope(&state.config.api.tokens, token) else {
+        return unauthorized("Invalid API token");
+    };
+
+    req.extensions_mut().insert(AuthorizedScope(scope));
+    next.run(req).await
+}
+
+pub async fn require_admin(req: Request, next: Next) -> Response {
+    let scope = req.extensions().get::<AuthorizedScope>().map(|AuthorizedScope(scope)| *scope);
+    if scope_is_admin(scope) {
+        next.run(req).await
+    } else {
+        (StatusCode::FORBIDDEN, "Admin-scoped token required").into_response()
+    }
+}
+
+fn bearer_token(req: &Request) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Looks up the scope of whichever configured token hashes to `token`, or
+/// `None` if it doesn't match any of them.
+fn resolve_scope(tokens: &[ApiToken], token: &str) -> Option<TokenScope> {
+    let token_hash = blake3::hash(token.as_bytes()).to_hex().to_string();
+    tokens.iter().find(|t| t.hash == token_hash).map(|t| t.scope)
+}
+
+fn scope_is_admin(scope: Option<TokenScope>) -> bool {
+    matches!(scope, Some(TokenScope::Admin))
+}
+
+fn unauthorized(message: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, message.to_string()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::Request as HttpRequest;
+
+    fn request_with_auth_header(value: Option<&str>) -> HttpRequest {
+        let mut builder = HttpRequest::builder().uri("/");
+        if let Some(value) = value {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    fn token(token: &str, scope: TokenScope) -> ApiToken {
+        ApiToken { hash: blake3::hash(token.as_bytes()).to_hex().to_string(), scope }
+    }
+
+    #[test]
+    fn bearer_token_missing_header() {
+        let req = request_with_auth_header(None);
+        assert_eq!(bearer_token(&req), None);
+    }
+
+    #[test]
+    fn bearer_token_rejects_non_bearer_scheme() {
+        let req = request_with_auth_header(Some("Basic dXNlcjpwYXNz"));
+        assert_eq!(bearer_token(&req), None);
+    }
+
+    #[test]
+    fn bearer_token_extracts_token() {
+        let req = request_with_auth_header(Some("Bearer secret-token"));
+        assert_eq!(bearer_token(&req), Some("secret-token"));
+    }
+
+    #[test]
+    fn resolve_scope_rejects_unknown_token() {
+        let tokens = vec![token("known-token", TokenScope::ReadOnly)];
+        assert_eq!(resolve_scope(&tokens, "unknown-token"), None);
+    }
+
+    #[test]
+    fn resolve_scope_finds_matching_token() {
+        let tokens = vec![token("known-token", TokenScope::Admin)];
+        assert_eq!(resolve_scope(&tokens, "known-token"), Some(TokenScope::Admin));
+    }
+
+    #[test]
+    fn read_only_token_is_not_admin() {
+        assert!(!scope_is_admin(Some(TokenScope::ReadOnly)));
+    }
+
+    #[test]
+    fn admin_token_is_admin() {
+        assert!(scope_is_admin(Some(TokenScope::Admin)));
+    }
+
+    #[test]
+    fn missing_scope_is_not_admin() {
+        assert!(!scope_is_admin(None));
+    }
+}
@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::io::Read;
 use std::path::PathBuf;
 
+use crate::chunker::{self, Chunk};
 use crate::crypto::Crypto;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +13,10 @@ pub struct BackupVersion {
     pub timestamp: DateTime<Utc>,
     pub file_hash: String,
     pub block_hashes: Vec<String>,
+    /// Content-defined chunks backing `block_hashes`, in order. Carries the
+    /// byte range each hash covers so storage can address variable-length
+    /// blocks instead of assuming a fixed block size.
+    pub chunks: Vec<Chunk>,
     pub metadata: FileMetadata,
 }
 
@@ -23,55 +29,76 @@ pub struct FileMetadata {
 
 pub struct BackupEngine {
     crypto: Crypto,
-    block_size: usize,
 }
 
 impl BackupEngine {
-    pub fn new(block_size: usize) -> Self {
+    pub fn new() -> Self {
         BackupEngine {
             crypto: Crypto::new(),
-            block_size,
         }
     }
 
     pub fn create_backup(&self, file_path: &PathBuf) -> Result<BackupVersion> {
-        // Read file contents
-        let data = std::fs::read(file_path)
+        let metadata = self.file_metadata(file_path)?;
+        let file = std::fs::File::open(file_path)
             .context(format!("Failed to read file: {:?}", file_path))?;
 
-        // Calculate file hash
-        let file_hash = self.crypto.hash(&data);
-
-        // Split into blocks and hash each
-        let block_hashes = self.create_block_hashes(&data);
+        self.backup_reader(file, metadata, |_chunk, _data| Ok(()))
+    }
 
-        // Get file metadata
-        let metadata = self.get_metadata(file_path)?;
+    /// Stream `reader` through the chunker and a running BLAKE3 hasher at the
+    /// same time, calling `on_chunk` with each completed chunk's metadata and
+    /// bytes as soon as it's cut so the caller can compress/encrypt/store it
+    /// immediately instead of holding the whole file in memory. Works for any
+    /// `Read` source, not just files on disk.
+    pub fn backup_reader<R: Read>(
+        &self,
+        mut reader: R,
+        metadata: FileMetadata,
+        mut on_chunk: impl FnMut(&Chunk, &[u8]) -> Result<()>,
+    ) -> Result<BackupVersion> {
+        let mut chunks = Vec::new();
+        let mut offset = 0u64;
+
+        let file_hash = chunker::chunk_reader(&mut reader, |data, hash| {
+            let chunk = Chunk {
+                offset,
+                len: data.len() as u64,
+                hash: hash.to_string(),
+            };
+            on_chunk(&chunk, data)?;
+            offset += chunk.len;
+            chunks.push(chunk);
+            Ok(())
+        })?;
+
+        let block_hashes = chunks.iter().map(|c| c.hash.clone()).collect();
 
         Ok(BackupVersion {
             version: 1, // Will be set by storage layer
             timestamp: Utc::now(),
             file_hash,
             block_hashes,
+            chunks,
             metadata,
         })
     }
 
-    pub fn encrypt_block(&self, block: &[u8], key: &[u8]) -> Result<Vec<u8>> {
-        self.crypto.encrypt(block, key)
+    /// Encrypt one chunk under a key/nonce/AAD unique to `chunk_digest`, so
+    /// the ciphertext stored at `get_block_path(chunk_digest)` only opens
+    /// against that digest. See [`Crypto::encrypt_chunk`].
+    pub fn encrypt_block(&self, block: &[u8], master_key: &[u8], chunk_digest: &str) -> Result<Vec<u8>> {
+        self.crypto.encrypt_chunk(block, master_key, chunk_digest)
     }
 
-    pub fn decrypt_block(&self, encrypted_block: &[u8], key: &[u8]) -> Result<Vec<u8>> {
-        self.crypto.decrypt(encrypted_block, key)
-    }
-
-    fn create_block_hashes(&self, data: &[u8]) -> Vec<String> {
-        data.chunks(self.block_size)
-            .map(|chunk| self.crypto.hash(chunk))
-            .collect()
+    pub fn decrypt_block(&self, encrypted_block: &[u8], master_key: &[u8], chunk_digest: &str) -> Result<Vec<u8>> {
+        self.crypto.decrypt_chunk(encrypted_block, master_key, chunk_digest)
     }
 
-    fn get_metadata(&self, file_path: &PathBuf) -> Result<FileMetadata> {
+    /// Read `file_path`'s size, Unix permission bits and modification time.
+    /// Exposed so callers that stream backups via [`Self::backup_reader`]
+    /// (which takes a generic `Read`, not a path) can still attach metadata.
+    pub fn file_metadata(&self, file_path: &std::path::Path) -> Result<FileMetadata> {
         let metadata = std::fs::metadata(file_path)
             .context("Failed to get file metadata")?;
 
@@ -95,23 +122,22 @@ impl BackupEngine {
         })
     }
 
+    /// Re-chunk `current_data` and return the chunks whose hash isn't already
+    /// present in `previous_blocks`. Because chunk boundaries are
+    /// content-defined, an edit near the start of the file no longer shifts
+    /// every later chunk's hash, so this reports only the chunks that
+    /// actually changed instead of "everything after the edit point".
     pub fn calculate_incremental_changes(
         &self,
         current_data: &[u8],
         previous_blocks: &[String],
-    ) -> Vec<usize> {
-        let current_hashes = self.create_block_hashes(current_data);
-        
-        current_hashes
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, hash)| {
-                if idx >= previous_blocks.len() || hash != &previous_blocks[idx] {
-                    Some(idx)
-                } else {
-                    None
-                }
-            })
+    ) -> Vec<Chunk> {
+        let previous: std::collections::HashSet<&str> =
+            previous_blocks.iter().map(|h| h.as_str()).collect();
+
+        chunker::chunk_data(current_data)
+            .into_iter()
+            .filter(|chunk| !previous.contains(chunk.hash.as_str()))
             .collect()
     }
 
@@ -135,33 +161,49 @@ mod tests {
         let mut file = NamedTempFile::new().unwrap();
         file.write_all(b"Test medical data").unwrap();
 
-        let engine = BackupEngine::new(4096);
+        let engine = BackupEngine::new();
         let backup = engine.create_backup(&file.path().to_path_buf()).unwrap();
 
         assert!(!backup.file_hash.is_empty());
         assert!(!backup.block_hashes.is_empty());
+        assert_eq!(backup.block_hashes.len(), backup.chunks.len());
     }
 
     #[test]
     fn test_incremental_changes() {
-        let engine = BackupEngine::new(10);
-        
-        let old_data = b"Hello World!!!";
-        let new_data = b"Hello Rust!!!!";
-        
-        let old_hashes = old_data.chunks(10)
-            .map(|chunk| engine.crypto.hash(chunk))
-            .collect::<Vec<_>>();
-        
-        let changes = engine.calculate_incremental_changes(new_data, &old_hashes);
-        
-        // Second block should be different
+        let engine = BackupEngine::new();
+
+        // Pseudo-random bytes, so the chunker finds content-defined cut
+        // points (a run of identical bytes never produces one).
+        let mut state = 0x1234_5678u32;
+        let old_data: Vec<u8> = (0..1_000_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state >> 24) as u8
+            })
+            .collect();
+        let mut new_data = old_data.clone();
+        new_data.truncate(500_000);
+        new_data.push(b'B');
+        new_data.extend_from_slice(&old_data[500_000..]);
+
+        let old_hashes: Vec<String> = chunker::chunk_data(&old_data)
+            .into_iter()
+            .map(|c| c.hash)
+            .collect();
+
+        let changes = engine.calculate_incremental_changes(&new_data, &old_hashes);
+
+        // Only the chunk(s) around the inserted byte should be reported.
         assert!(!changes.is_empty());
+        assert!(changes.len() < old_hashes.len());
     }
 
     #[test]
     fn test_compression() {
-        let engine = BackupEngine::new(4096);
+        let engine = BackupEngine::new();
         let data = vec![b'A'; 10000]; // Highly compressible
 
         let compressed = engine.compress(&data).unwrap();
@@ -0,0 +1,149 @@
+//! Bloom filter cascade for the ransomware detector's known-good whitelist.
+//!
+//! Built CRLite-style: level 0 is a Bloom filter over the whitelisted
+//! ("included") digests. Querying a sampled corpus of known threat/unknown
+//! ("excluded") digests against it collects false positives into level 1's
+//! included set; the whitelisted digests that falsely hit level 1 become
+//! level 2's included set, and so on, alternating until a level produces no
+//! false positives. Lookup walks the levels, flipping the meaning of
+//! present/absent at each one, giving exact membership for both known sets
+//! in a fraction of the space a `HashSet` of every digest would need.
+
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+/// Bits-per-element and hash-function count tuned for roughly a 1%
+/// false-positive rate per level (`-ln(p) / ln(2)^2` bits, `ln(2) * bits`
+/// hashes).
+const BITS_PER_ELEMENT: usize = 10;
+const NUM_HASHES: u32 = 7;
+
+struct BloomFilter {
+    bits: Vec<bool>,
+}
+
+impl BloomFilter {
+    fn with_capacity(expected_items: usize) -> Self {
+        let num_bits = (expected_items * BITS_PER_ELEMENT).max(64);
+        BloomFilter { bits: vec![false; num_bits] }
+    }
+
+    /// Kirsch-Mitzenmacher double hashing: derive `NUM_HASHES` bit indices
+    /// from two base hashes instead of hashing `item` `NUM_HASHES` times.
+    fn indices(&self, item: &str) -> impl Iterator<Item = usize> + '_ {
+        let mut hasher_a = DefaultHasher::new();
+        item.hash(&mut hasher_a);
+        let h1 = hasher_a.finish();
+
+        let mut hasher_b = DefaultHasher::new();
+        item.hash(&mut hasher_b);
+        hasher_b.write_u8(0xA5); // perturb so h2 != h1
+        let h2 = hasher_b.finish();
+
+        let num_bits = self.bits.len() as u64;
+        (0..NUM_HASHES).map(move |i| (h1.wrapping_add((i as u64).wrapping_mul(h2)) % num_bits) as usize)
+    }
+
+    fn insert(&mut self, item: &str) {
+        for idx in self.indices(item).collect::<Vec<_>>() {
+            self.bits[idx] = true;
+        }
+    }
+
+    fn contains(&self, item: &str) -> bool {
+        self.indices(item).all(|idx| self.bits[idx])
+    }
+}
+
+/// Exact membership test over a known "included" set vs. a known "excluded"
+/// set, built as an alternating cascade of Bloom filters.
+pub struct BloomCascade {
+    levels: Vec<BloomFilter>,
+}
+
+impl BloomCascade {
+    /// `included` is the whitelist (approved digests). `excluded` is a
+    /// sampled corpus of digests known *not* to be whitelisted, used only to
+    /// calibrate the cascade; an empty `excluded` set still yields a
+    /// correct single-level filter, it just can't be refined further.
+    pub fn build(included: &[String], excluded: &[String]) -> Self {
+        let mut levels = Vec::new();
+        let mut level_included = included.to_vec();
+        let mut level_opposite = excluded.to_vec();
+
+        loop {
+            let mut filter = BloomFilter::with_capacity(level_included.len());
+            for item in &level_included {
+                filter.insert(item);
+            }
+
+            let false_positives: Vec<String> =
+                level_opposite.iter().filter(|item| filter.contains(item)).cloned().collect();
+
+            levels.push(filter);
+
+            if false_positives.is_empty() {
+                break;
+            }
+
+            let next_opposite = level_included;
+            level_included = false_positives;
+            level_opposite = next_opposite;
+        }
+
+        BloomCascade { levels }
+    }
+
+    /// Walks the cascade, alternating the meaning of each level: even
+    /// levels hold "whitelisted" sets, odd levels hold "not-whitelisted"
+    /// sets. A Bloom filter never false-negatives, so absence at any level
+    /// is a definitive answer; presence is ambiguous and resolved by the
+    /// next level, with the final level trusted outright since by
+    /// construction it has no unresolved false positives left to escalate.
+    pub fn contains(&self, digest: &str) -> bool {
+        for (i, filter) in self.levels.iter().enumerate() {
+            let present = filter.contains(digest);
+            let is_last = i == self.levels.len() - 1;
+            if !present {
+                return i % 2 == 1;
+            }
+            if is_last {
+                return i % 2 == 0;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitelisted_digests_are_found() {
+        let included = vec!["aaa".to_string(), "bbb".to_string(), "ccc".to_string()];
+        let excluded = vec!["ddd".to_string(), "eee".to_string(), "fff".to_string()];
+        let cascade = BloomCascade::build(&included, &excluded);
+
+        for digest in &included {
+            assert!(cascade.contains(digest), "{digest} should be whitelisted");
+        }
+    }
+
+    #[test]
+    fn excluded_digests_are_not_whitelisted() {
+        let included = vec!["aaa".to_string(), "bbb".to_string()];
+        let excluded = vec!["ccc".to_string(), "ddd".to_string(), "eee".to_string()];
+        let cascade = BloomCascade::build(&included, &excluded);
+
+        for digest in &excluded {
+            assert!(!cascade.contains(digest), "{digest} should not be whitelisted");
+        }
+    }
+
+    #[test]
+    fn empty_cascade_whitelists_nothing() {
+        let cascade = BloomCascade::build(&[], &[]);
+        assert!(!cascade.contains("anything"));
+    }
+}
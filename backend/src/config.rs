@@ -9,6 +9,10 @@ pub struct Config {
     pub backup: BackupConfig,
     pub encryption: EncryptionConfig,
     pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub response: ResponseConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +28,15 @@ pub struct DetectionConfig {
     pub rapid_change_threshold: usize,
     pub suspicious_extensions: Vec<String>,
     pub time_window_seconds: u64,
+    /// BLAKE3 digests of approved files (already-encrypted DICOM, zip
+    /// exports, ...) exempted from the entropy check so they don't trip the
+    /// ransomware heuristic just for being legitimately high-entropy.
+    #[serde(default)]
+    pub whitelist_hashes: Vec<String>,
+    /// Sampled digests of known threat/unknown files, used only to
+    /// calibrate the Bloom filter cascade built from `whitelist_hashes`.
+    #[serde(default)]
+    pub whitelist_sample_excluded: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,12 +46,42 @@ pub struct BackupConfig {
     pub block_size: usize,
     pub compression_enabled: bool,
     pub storage_path: String,
+    /// Which `StorageBackend` impl `main` constructs: `"local"` (default,
+    /// writes under `storage_path` on this host) or `"s3"` (Garage/S3
+    /// bucket, so backups stay reachable even if the host is compromised).
+    #[serde(default = "default_backup_backend")]
+    pub backend: String,
+    /// Bucket holding blocks/index when `backend = "s3"`.
+    #[serde(default)]
+    pub s3_bucket: Option<String>,
+    /// Custom endpoint for Garage or another S3-compatible service; omit
+    /// for AWS S3.
+    #[serde(default)]
+    pub s3_endpoint: Option<String>,
+    #[serde(default = "default_s3_region")]
+    pub s3_region: String,
+    #[serde(default)]
+    pub s3_access_key_id: Option<String>,
+    #[serde(default)]
+    pub s3_secret_access_key: Option<String>,
+}
+
+fn default_backup_backend() -> String {
+    "local".to_string()
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptionConfig {
     pub algorithm: String,
     pub key_derivation: String,
+    /// Path to the passphrase-wrapped master key (salt + ciphertext JSON).
+    /// Created on first run and reused afterwards; unlocking it requires the
+    /// `MEDGUARD_PASSPHRASE` environment variable.
+    pub master_key_path: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +91,55 @@ pub struct AlertsConfig {
     pub sms_numbers: Vec<String>,
 }
 
+/// What a validated bearer token is allowed to do: `ReadOnly` tokens may
+/// only hit observational routes (`/api/status`, `/api/alerts`, ...);
+/// `Admin` tokens may also call destructive ones (`/api/restore`, mount).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    ReadOnly,
+    Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// BLAKE3 hex digest of the bearer token. The plaintext token itself is
+    /// never stored, so a leaked config file doesn't leak live credentials.
+    pub hash: String,
+    pub scope: TokenScope,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+}
+
+/// Active-response containment, triggered when a `ThreatAlert::score`
+/// crosses `min_score`. Each action is independently gated so an operator
+/// can e.g. isolate the network but never auto-kill a process; `dry_run`
+/// overrides both and only logs what would have happened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseConfig {
+    pub min_score: u32,
+    pub kill_process: bool,
+    pub isolate_network: bool,
+    pub dry_run: bool,
+}
+
+impl Default for ResponseConfig {
+    fn default() -> Self {
+        ResponseConfig {
+            // Matches the hardcoded alert threshold in `detector.rs`, so
+            // containment fires on any alert by default unless raised.
+            min_score: 70,
+            kill_process: false,
+            isolate_network: false,
+            dry_run: true,
+        }
+    }
+}
+
 impl Config {
     pub fn load(path: &str) -> Result<Self> {
         let content = fs::read_to_string(path)?;
@@ -81,6 +173,8 @@ impl Default for Config {
                     ".enc".to_string(),
                 ],
                 time_window_seconds: 60,
+                whitelist_hashes: vec![],
+                whitelist_sample_excluded: vec![],
             },
             backup: BackupConfig {
                 incremental_interval: 60,
@@ -88,16 +182,25 @@ impl Default for Config {
                 block_size: 4096,
                 compression_enabled: true,
                 storage_path: "./backups".to_string(),
+                backend: default_backup_backend(),
+                s3_bucket: None,
+                s3_endpoint: None,
+                s3_region: default_s3_region(),
+                s3_access_key_id: None,
+                s3_secret_access_key: None,
             },
             encryption: EncryptionConfig {
                 algorithm: "AES-256-GCM".to_string(),
                 key_derivation: "Argon2id".to_string(),
+                master_key_path: "./backups/master.key.json".to_string(),
             },
             alerts: AlertsConfig {
                 webhook_url: None,
                 email_recipients: vec![],
                 sms_numbers: vec![],
             },
+            api: ApiConfig::default(),
+            response: ResponseConfig::default(),
         }
     }
 }
@@ -1,14 +1,26 @@
 use anyhow::{Context, Result};
 use ring::aead::{Aad, BoundKey, Nonce, NonceSequence, OpeningKey, SealingKey, UnboundKey, AES_256_GCM};
 use ring::error::Unspecified;
+use ring::hkdf::{Salt, HKDF_SHA256};
 use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
 
 const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
 
 pub struct Crypto {
     rng: SystemRandom,
 }
 
+/// A master encryption key wrapped under an Argon2id-derived passphrase key,
+/// suitable for storing on disk: without the passphrase, `ciphertext` alone
+/// doesn't recover the master key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub salt: String,
+    pub ciphertext: String,
+}
+
 impl Crypto {
     pub fn new() -> Self {
         Crypto {
@@ -24,6 +36,43 @@ impl Crypto {
     }
 
     pub fn encrypt(&self, data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        self.seal(data, key, &[])
+    }
+
+    pub fn decrypt(&self, encrypted_data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+        self.open(encrypted_data, key, &[])
+    }
+
+    /// Encrypt one content-addressed chunk. Derives a subkey unique to this
+    /// chunk from `master_key` via HKDF (so the same master key never seals
+    /// two different chunks under the same AES-GCM key/nonce pairing) and
+    /// binds `chunk_digest` into the AAD, so the ciphertext only opens under
+    /// the digest it was sealed for and can't be silently swapped for
+    /// another chunk's ciphertext on disk.
+    pub fn encrypt_chunk(&self, data: &[u8], master_key: &[u8], chunk_digest: &str) -> Result<Vec<u8>> {
+        let subkey = self.derive_chunk_key(master_key, chunk_digest)?;
+        self.seal(data, &subkey, chunk_digest.as_bytes())
+    }
+
+    /// Inverse of [`Self::encrypt_chunk`].
+    pub fn decrypt_chunk(&self, encrypted_data: &[u8], master_key: &[u8], chunk_digest: &str) -> Result<Vec<u8>> {
+        let subkey = self.derive_chunk_key(master_key, chunk_digest)?;
+        self.open(encrypted_data, &subkey, chunk_digest.as_bytes())
+    }
+
+    fn derive_chunk_key(&self, master_key: &[u8], chunk_digest: &str) -> Result<Vec<u8>> {
+        let salt = Salt::new(HKDF_SHA256, b"medguard-chunk-key");
+        let prk = salt.extract(master_key);
+        let okm = prk
+            .expand(&[chunk_digest.as_bytes()], HkdfKeyLen(KEY_LEN))
+            .map_err(|_| anyhow::anyhow!("Failed to derive chunk subkey"))?;
+        let mut subkey = vec![0u8; KEY_LEN];
+        okm.fill(&mut subkey)
+            .map_err(|_| anyhow::anyhow!("Failed to fill chunk subkey"))?;
+        Ok(subkey)
+    }
+
+    fn seal(&self, data: &[u8], key: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
         // Generate random nonce
         let mut nonce_bytes = [0u8; NONCE_LEN];
         self.rng.fill(&mut nonce_bytes)
@@ -38,17 +87,17 @@ impl Crypto {
         // Prepare output buffer
         let mut encrypted = data.to_vec();
         sealing_key
-            .seal_in_place_append_tag(Aad::empty(), &mut encrypted)
+            .seal_in_place_append_tag(Aad::from(aad), &mut encrypted)
             .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
 
         // Prepend nonce to encrypted data
         let mut output = nonce_bytes.to_vec();
         output.extend_from_slice(&encrypted);
-        
+
         Ok(output)
     }
 
-    pub fn decrypt(&self, encrypted_data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    fn open(&self, encrypted_data: &[u8], key: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
         if encrypted_data.len() < NONCE_LEN {
             anyhow::bail!("Invalid encrypted data: too short");
         }
@@ -67,9 +116,9 @@ impl Crypto {
         // Decrypt
         let mut decrypted = ciphertext.to_vec();
         let decrypted_data = opening_key
-            .open_in_place(Aad::empty(), &mut decrypted)
+            .open_in_place(Aad::from(aad), &mut decrypted)
             .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
-        
+
         Ok(decrypted_data.to_vec())
     }
 
@@ -83,7 +132,7 @@ impl Crypto {
         use argon2::{Argon2, PasswordHasher};
         use argon2::password_hash::SaltString;
 
-        // Convert salt to SaltString  
+        // Convert salt to SaltString
         let salt_string = SaltString::encode_b64(salt)
             .map_err(|_| anyhow::anyhow!("Failed to encode salt"))?;
 
@@ -95,9 +144,58 @@ impl Crypto {
         // Extract the hash bytes
         let hash = password_hash.hash
             .ok_or_else(|| anyhow::anyhow!("No hash in password hash"))?;
-        
+
         Ok(hash.as_bytes().to_vec())
     }
+
+    /// Wrap `master_key` under a passphrase: derive a wrapping key via
+    /// Argon2id over a fresh random salt, then encrypt the master key with
+    /// it. Safe to persist `WrappedKey` to disk; the passphrase is required
+    /// to recover `master_key`.
+    pub fn wrap_master_key(&self, master_key: &[u8], passphrase: &str) -> Result<WrappedKey> {
+        let mut salt = [0u8; 16];
+        self.rng.fill(&mut salt)
+            .map_err(|_| anyhow::anyhow!("Failed to generate salt"))?;
+
+        let wrapping_key = self.derive_key(passphrase, &salt)?;
+        let ciphertext = self.encrypt(master_key, &wrapping_key[..KEY_LEN])?;
+
+        Ok(WrappedKey {
+            salt: hex_encode(&salt),
+            ciphertext: hex_encode(&ciphertext),
+        })
+    }
+
+    /// Inverse of [`Self::wrap_master_key`].
+    pub fn unwrap_master_key(&self, wrapped: &WrappedKey, passphrase: &str) -> Result<Vec<u8>> {
+        let salt = hex_decode(&wrapped.salt).context("Invalid wrapped-key salt")?;
+        let ciphertext = hex_decode(&wrapped.ciphertext).context("Invalid wrapped-key ciphertext")?;
+
+        let wrapping_key = self.derive_key(passphrase, &salt)?;
+        self.decrypt(&ciphertext, &wrapping_key[..KEY_LEN])
+    }
+}
+
+struct HkdfKeyLen(usize);
+
+impl ring::hkdf::KeyType for HkdfKeyLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Result<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        anyhow::bail!("Invalid hex string length");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).context("Invalid hex digit"))
+        .collect()
 }
 
 // NonceSequence implementation for single-use nonce
@@ -154,4 +252,30 @@ mod tests {
 
         assert_ne!(hash1, hash2);
     }
+
+    #[test]
+    fn chunk_encryption_binds_to_its_own_digest() {
+        let crypto = Crypto::new();
+        let master_key = crypto.generate_key().unwrap();
+        let plaintext = b"chunk bytes";
+
+        let encrypted = crypto.encrypt_chunk(plaintext, &master_key, "digest-a").unwrap();
+        let decrypted = crypto.decrypt_chunk(&encrypted, &master_key, "digest-a").unwrap();
+        assert_eq!(plaintext, decrypted.as_slice());
+
+        // Ciphertext sealed for one digest must not open under another.
+        assert!(crypto.decrypt_chunk(&encrypted, &master_key, "digest-b").is_err());
+    }
+
+    #[test]
+    fn wrapped_master_key_round_trips_under_its_passphrase() {
+        let crypto = Crypto::new();
+        let master_key = crypto.generate_key().unwrap();
+
+        let wrapped = crypto.wrap_master_key(&master_key, "correct horse").unwrap();
+        let recovered = crypto.unwrap_master_key(&wrapped, "correct horse").unwrap();
+        assert_eq!(master_key, recovered);
+
+        assert!(crypto.unwrap_master_key(&wrapped, "wrong passphrase").is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use crate::bloom::BloomCascade;
 use crate::config::Config;
 use crate::monitor::FileEvent;
 use chrono::{DateTime, Utc};
@@ -14,6 +15,11 @@ pub struct ThreatAlert {
     pub description: String,
     pub file_path: String,
     pub threat_type: String,
+    /// Containment actions executed (or, in dry-run mode, that would have
+    /// been executed) in response to this alert. Empty if the score stayed
+    /// below `[response] min_score`, or containment isn't configured.
+    #[serde(default)]
+    pub actions_taken: Vec<String>,
 }
 
 pub struct ThreatDetector {
@@ -21,15 +27,22 @@ pub struct ThreatDetector {
     event_window: VecDeque<FileEvent>,
     file_hashes: HashMap<PathBuf, String>,
     files_monitored: usize,
+    /// Known-good membership index, built once from `config.detection`'s
+    /// whitelist digests. Checked before the entropy rule so approved
+    /// already-encrypted/compressed files don't generate noise.
+    whitelist: BloomCascade,
 }
 
 impl ThreatDetector {
     pub fn new(config: Config) -> Self {
+        let whitelist =
+            BloomCascade::build(&config.detection.whitelist_hashes, &config.detection.whitelist_sample_excluded);
         ThreatDetector {
             config,
             event_window: VecDeque::new(),
             file_hashes: HashMap::new(),
             files_monitored: 0,
+            whitelist,
         }
     }
 
@@ -51,7 +64,7 @@ impl ThreatDetector {
         }
     }
 
-    pub fn check_threat(&self) -> Option<ThreatAlert> {
+    pub async fn check_threat(&self) -> Option<ThreatAlert> {
         let mut threat_score = 0u32;
         let mut threat_reasons = Vec::new();
 
@@ -79,13 +92,30 @@ impl ThreatDetector {
             threat_reasons.push(format!("Suspicious extensions: {} files", suspicious_count));
         }
 
-        // Check 3: Entropy analysis
+        // Check 3: Entropy analysis, skipped for files already on the
+        // known-good whitelist (legitimately encrypted/compressed content
+        // would otherwise trip this on every edit).
         if let Some(last_event) = self.event_window.back() {
             if last_event.path.exists() {
-                if let Ok(entropy) = calculate_entropy(&last_event.path) {
-                    if entropy > self.config.detection.entropy_threshold {
-                        threat_score += 40;
-                        threat_reasons.push(format!("High entropy detected: {:.2} bits/byte", entropy));
+                // `file_digest` reads the whole file, which can block for a
+                // while on a large one; run it on a blocking-pool thread so
+                // a big touched file can't stall the rest of this task
+                // (alerting/backup dispatch for every other file) while
+                // we're waiting on it.
+                let path = last_event.path.clone();
+                let whitelisted = tokio::task::spawn_blocking(move || file_digest(&path))
+                    .await
+                    .ok()
+                    .and_then(|r| r.ok())
+                    .map(|digest| self.whitelist.contains(&digest))
+                    .unwrap_or(false);
+
+                if !whitelisted {
+                    if let Ok(entropy) = calculate_entropy(&last_event.path) {
+                        if entropy > self.config.detection.entropy_threshold {
+                            threat_score += 40;
+                            threat_reasons.push(format!("High entropy detected: {:.2} bits/byte", entropy));
+                        }
                     }
                 }
             }
@@ -107,6 +137,7 @@ impl ThreatDetector {
                 description: threat_reasons.join("; "),
                 file_path: last_event.path.to_string_lossy().to_string(),
                 threat_type: "Ransomware".to_string(),
+                actions_taken: Vec::new(),
             })
         } else {
             None
@@ -131,6 +162,14 @@ impl ThreatDetector {
     }
 }
 
+/// Whole-file BLAKE3 digest, matching how backup digests are computed
+/// elsewhere (`chunker.rs`, `storage.rs`), so whitelist entries line up with
+/// the same hash an operator would read off a backed-up file.
+fn file_digest(path: &PathBuf) -> Result<String, std::io::Error> {
+    let bytes = std::fs::read(path)?;
+    Ok(blake3::hash(&bytes).to_hex().to_string())
+}
+
 fn calculate_entropy(path: &PathBuf) -> Result<f64, std::io::Error> {
     let mut file = File::open(path)?;
     let mut buffer = vec![0u8; 8192]; // Read first 8KB for performance
@@ -7,30 +7,42 @@ use axum::{
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 use tracing::{info, warn};
 use tracing_subscriber;
 
+mod auth;
 mod backup;
+mod bloom;
+mod chunker;
 mod config;
 mod crypto;
 mod detector;
 mod monitor;
+mod mount;
+mod response;
+mod s3_storage;
+mod sdnotify;
 mod storage;
 
 use crate::config::Config;
 use crate::detector::{ThreatAlert, ThreatDetector};
 use crate::monitor::FileMonitor;
-use crate::storage::Storage;
+use crate::mount::MountHandle;
+use crate::response::NftablesFirewall;
+use crate::s3_storage::S3Storage;
+use crate::storage::{LocalStorage, StorageBackend};
 
 #[derive(Clone)]
-struct AppState {
+pub(crate) struct AppState {
     detector: Arc<RwLock<ThreatDetector>>,
-    storage: Arc<Storage>,
+    storage: Arc<dyn StorageBackend>,
     alerts: Arc<RwLock<Vec<ThreatAlert>>>,
-    config: Arc<Config>,
+    pub(crate) config: Arc<Config>,
+    active_mounts: Arc<RwLock<HashMap<String, MountHandle>>>,
 }
 
 #[derive(Serialize)]
@@ -60,6 +72,23 @@ struct RestoreResponse {
     message: String,
 }
 
+#[derive(Deserialize)]
+struct MountRequest {
+    version: u64,
+    mountpoint: String,
+}
+
+#[derive(Deserialize)]
+struct UnmountRequest {
+    mountpoint: String,
+}
+
+#[derive(Serialize)]
+struct MountResponse {
+    success: bool,
+    message: String,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
@@ -77,9 +106,22 @@ async fn main() -> Result<()> {
     });
     info!("✓ Configuration loaded");
 
-    // Initialize storage
-    let storage = Arc::new(Storage::new(&config.backup.storage_path)?);
-    info!("✓ Storage initialized: {}", config.backup.storage_path);
+    // Initialize storage - which backend depends on `[backup] backend`.
+    let storage: Arc<dyn StorageBackend> = match config.backup.backend.as_str() {
+        "s3" => {
+            let s3 = S3Storage::new(&config.backup, &config.encryption).await?;
+            info!("✓ Storage initialized: s3://{}", config.backup.s3_bucket.as_deref().unwrap_or("?"));
+            Arc::new(s3)
+        }
+        other => {
+            if other != "local" {
+                warn!("Unknown backup.backend {:?}, falling back to local", other);
+            }
+            let local = LocalStorage::new(&config.backup.storage_path, &config.encryption, &config.backup)?;
+            info!("✓ Storage initialized: {}", config.backup.storage_path);
+            Arc::new(local)
+        }
+    };
 
     // Initialize threat detector
     let detector = Arc::new(RwLock::new(ThreatDetector::new(config.clone())));
@@ -94,6 +136,7 @@ async fn main() -> Result<()> {
         storage: storage.clone(),
         alerts: alerts.clone(),
         config: Arc::new(config.clone()),
+        active_mounts: Arc::new(RwLock::new(HashMap::new())),
     };
 
     // Start file monitoring
@@ -106,13 +149,19 @@ async fn main() -> Result<()> {
 
     info!("✓ File monitoring started for {} paths", config.monitoring.watch_paths.len());
 
-    // Build REST API
+    // Build REST API. Destructive routes additionally require an
+    // admin-scoped token; every route requires at least a valid one.
     let app = Router::new()
         .route("/api/status", get(get_status))
         .route("/api/alerts", get(get_alerts))
         .route("/api/backups", get(get_backups))
-        .route("/api/restore", post(restore_file))
+        .route("/api/restore", post(restore_file).route_layer(axum::middleware::from_fn(auth::require_admin)))
+        .route("/api/mount", post(mount_backup).route_layer(axum::middleware::from_fn(auth::require_admin)))
+        .route("/api/unmount", post(unmount_backup).route_layer(axum::middleware::from_fn(auth::require_admin)))
+        .route("/api/stats", get(get_stats))
+        .route("/api/verify", post(verify_backups).route_layer(axum::middleware::from_fn(auth::require_admin)))
         .route("/api/metrics", get(get_metrics))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), auth::require_token))
         .layer(CorsLayer::permissive())
         .with_state(state);
 
@@ -124,11 +173,39 @@ async fn main() -> Result<()> {
     info!("   API: http://localhost:8080/api/status");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    if let Err(e) = sdnotify::notify_stopping() {
+        warn!("sd_notify STOPPING notification failed: {}", e);
+    }
 
     Ok(())
 }
 
+/// Resolves once SIGINT or SIGTERM is received, so the server can shut down
+/// cleanly and tell systemd it's stopping instead of just disappearing.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+        sigterm.recv().await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
 async fn start_monitoring(state: AppState) -> Result<()> {
     let config = state.config.clone();
     let detector = state.detector.clone();
@@ -136,29 +213,68 @@ async fn start_monitoring(state: AppState) -> Result<()> {
     let alerts = state.alerts.clone();
 
     let mut monitor = FileMonitor::new((*config).clone())?;
+    let firewall = NftablesFirewall;
 
-    while let Some(event) = monitor.next_event().await {
-        // Analyze event for threats
-        let mut detector_guard = detector.write().await;
-        detector_guard.process_event(&event);
-
-        if let Some(threat) = detector_guard.check_threat() {
-            warn!("🚨 THREAT DETECTED: Score {} - {}", threat.score, threat.description);
-            
-            // Store alert
-            let mut alerts_guard = alerts.write().await;
-            alerts_guard.push(threat.clone());
-            
-            // Keep only last 1000 alerts
-            if alerts_guard.len() > 1000 {
-                alerts_guard.drain(0..100);
+    // Storage, detector and the file watcher are all up at this point.
+    if let Err(e) = sdnotify::notify_ready() {
+        warn!("sd_notify READY notification failed: {}", e);
+    }
+    // Ping on its own timer rather than only from inside the event branch
+    // below: a quiet-but-healthy watch directory can go longer than
+    // `WatchdogSec` between filesystem events, and pinging only on events
+    // would make systemd kill a perfectly fine, idle service.
+    let mut watchdog_ticker = sdnotify::watchdog_interval().map(tokio::time::interval);
+
+    loop {
+        tokio::select! {
+            maybe_event = monitor.next_event() => {
+                let Some(event) = maybe_event else { break };
+
+                // Analyze event for threats
+                let mut detector_guard = detector.write().await;
+                detector_guard.process_event(&event);
+
+                if let Some(mut threat) = detector_guard.check_threat().await {
+                    warn!("🚨 THREAT DETECTED: Score {} - {}", threat.score, threat.description);
+
+                    if threat.score >= config.response.min_score {
+                        threat.actions_taken = response::contain(&event, &config.response, &firewall);
+                        for action in &threat.actions_taken {
+                            warn!("🛡️  containment: {}", action);
+                        }
+                    }
+
+                    // Store alert
+                    let mut alerts_guard = alerts.write().await;
+                    alerts_guard.push(threat.clone());
+
+                    // Keep only last 1000 alerts
+                    if alerts_guard.len() > 1000 {
+                        alerts_guard.drain(0..100);
+                    }
+                }
+
+                // Perform incremental backup
+                if event.path.exists() {
+                    if let Err(e) = storage.backup_file(&event.path).await {
+                        warn!("Backup failed for {:?}: {}", event.path, e);
+                    }
+                }
             }
-        }
-
-        // Perform incremental backup
-        if event.path.exists() {
-            if let Err(e) = storage.backup_file(&event.path).await {
-                warn!("Backup failed for {:?}: {}", event.path, e);
+            _ = async {
+                match watchdog_ticker.as_mut() {
+                    Some(ticker) => { ticker.tick().await; }
+                    None => std::future::pending().await,
+                }
+            }, if watchdog_ticker.is_some() => {
+                let status = format!(
+                    "monitoring {} paths, {} threats",
+                    detector.read().await.files_monitored(),
+                    alerts.read().await.len(),
+                );
+                if let Err(e) = sdnotify::notify_watchdog_status(&status) {
+                    warn!("sd_notify watchdog ping failed: {}", e);
+                }
             }
         }
     }
@@ -223,6 +339,89 @@ async fn restore_file(
     }
 }
 
+/// Mounts every file whose backup history includes `req.version` at
+/// `req.mountpoint` so an operator can browse a point-in-time snapshot
+/// before deciding what to restore.
+async fn mount_backup(
+    State(state): State<AppState>,
+    Json(req): Json<MountRequest>,
+) -> impl IntoResponse {
+    let mut mounts = state.active_mounts.write().await;
+    if mounts.contains_key(&req.mountpoint) {
+        return Json(MountResponse {
+            success: false,
+            message: format!("{} is already mounted", req.mountpoint),
+        });
+    }
+
+    let mountpoint = std::path::PathBuf::from(&req.mountpoint);
+    let backups = match state.storage.list_backups().await {
+        Ok(backups) => backups,
+        Err(e) => {
+            return Json(MountResponse {
+                success: false,
+                message: format!("Failed to list backups: {}", e),
+            })
+        }
+    };
+    match crate::mount::mount_version(state.storage.clone(), backups, req.version, &mountpoint) {
+        Ok(handle) => {
+            mounts.insert(req.mountpoint.clone(), handle);
+            Json(MountResponse {
+                success: true,
+                message: format!("Mounted version {} at {}", req.version, req.mountpoint),
+            })
+        }
+        Err(e) => Json(MountResponse {
+            success: false,
+            message: format!("Mount failed: {}", e),
+        }),
+    }
+}
+
+async fn unmount_backup(
+    State(state): State<AppState>,
+    Json(req): Json<UnmountRequest>,
+) -> impl IntoResponse {
+    let mut mounts = state.active_mounts.write().await;
+    match mounts.remove(&req.mountpoint) {
+        Some(handle) => {
+            handle.unmount();
+            Json(MountResponse {
+                success: true,
+                message: format!("Unmounted {}", req.mountpoint),
+            })
+        }
+        None => Json(MountResponse {
+            success: false,
+            message: format!("{} is not mounted", req.mountpoint),
+        }),
+    }
+}
+
+async fn get_stats(State(state): State<AppState>) -> impl IntoResponse {
+    match crate::storage::repo_stats(state.storage.as_ref()).await {
+        Ok(stats) => (StatusCode::OK, Json(serde_json::json!(stats))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
+/// Scrubs the full block store (decrypt, decompress, re-hash every block)
+/// and checks every referenced block is actually present. Heavy enough to
+/// gate behind an admin token like the other expensive routes.
+async fn verify_backups(State(state): State<AppState>) -> impl IntoResponse {
+    match crate::storage::verify_blocks(state.storage.as_ref()).await {
+        Ok(report) => (StatusCode::OK, Json(serde_json::json!(report))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
 async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
     let detector = state.detector.read().await;
     let alerts = state.alerts.read().await;
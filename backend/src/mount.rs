@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request,
+};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::backup::BackupVersion;
+use crate::storage::{BackupInfo, StorageBackend};
+
+const TTL: Duration = Duration::from_secs(1);
+const ROOT_INODE: u64 = 1;
+
+enum Node {
+    Dir { children: HashMap<String, u64> },
+    File { version: BackupVersion },
+}
+
+fn split_components(path: &str) -> Vec<&str> {
+    path.split('/').filter(|c| !c.is_empty()).collect()
+}
+
+/// Read-only FUSE view over every file that has a version numbered exactly
+/// `version` in its backup history. Files whose history doesn't include that
+/// version number are simply absent from the tree. Content is fetched and
+/// decrypted lazily, chunk by chunk, on `read()`.
+struct VersionFs {
+    nodes: HashMap<u64, Node>,
+    storage: Arc<dyn StorageBackend>,
+    // `read()` is called from a plain (non-async) FUSE callback, but block
+    // fetches go through `StorageBackend`'s async methods (the S3 backend
+    // needs real network I/O there) - so a runtime handle is captured at
+    // mount time and used to block on those calls from this background
+    // thread, the same bridging pattern rustapp/sentinel's mount uses.
+    rt: tokio::runtime::Handle,
+}
+
+impl VersionFs {
+    fn new(storage: Arc<dyn StorageBackend>, rt: tokio::runtime::Handle, backups: &[BackupInfo], version: u64) -> Self {
+        let mut nodes = HashMap::new();
+        nodes.insert(ROOT_INODE, Node::Dir { children: HashMap::new() });
+        let mut next_inode = ROOT_INODE + 1;
+
+        for info in backups {
+            let Some(matching) = info.versions.iter().find(|v| v.version == version) else {
+                continue;
+            };
+
+            let components = split_components(&info.file_path);
+            if components.is_empty() {
+                continue;
+            }
+
+            let mut parent = ROOT_INODE;
+            for (idx, name) in components.iter().enumerate() {
+                let is_last = idx == components.len() - 1;
+                let existing = match nodes.get_mut(&parent).unwrap() {
+                    Node::Dir { children } => children.get(*name).copied(),
+                    Node::File { .. } => None,
+                };
+                let child_inode = if let Some(inode) = existing {
+                    inode
+                } else {
+                    let inode = next_inode;
+                    next_inode += 1;
+                    let node = if is_last {
+                        Node::File { version: matching.clone() }
+                    } else {
+                        Node::Dir { children: HashMap::new() }
+                    };
+                    nodes.insert(inode, node);
+                    if let Node::Dir { children } = nodes.get_mut(&parent).unwrap() {
+                        children.insert(name.to_string(), inode);
+                    }
+                    inode
+                };
+                parent = child_inode;
+            }
+        }
+
+        VersionFs { nodes, storage, rt }
+    }
+
+    fn attr_for(&self, inode: u64) -> Option<FileAttr> {
+        let node = self.nodes.get(&inode)?;
+        Some(match node {
+            Node::Dir { .. } => FileAttr {
+                ino: inode,
+                size: 0,
+                blocks: 0,
+                atime: UNIX_EPOCH,
+                mtime: UNIX_EPOCH,
+                ctime: UNIX_EPOCH,
+                crtime: UNIX_EPOCH,
+                kind: FileType::Directory,
+                perm: 0o555,
+                nlink: 2,
+                uid: 0,
+                gid: 0,
+                rdev: 0,
+                blksize: 512,
+                flags: 0,
+            },
+            Node::File { version } => {
+                let mtime = version.metadata.modified.into();
+                FileAttr {
+                    ino: inode,
+                    size: version.metadata.size,
+                    blocks: version.metadata.size.div_ceil(512),
+                    atime: mtime,
+                    mtime,
+                    ctime: mtime,
+                    crtime: mtime,
+                    kind: FileType::RegularFile,
+                    perm: (version.metadata.permissions & 0o777) as u16,
+                    nlink: 1,
+                    uid: 0,
+                    gid: 0,
+                    rdev: 0,
+                    blksize: 512,
+                    flags: 0,
+                }
+            }
+        })
+    }
+}
+
+impl Filesystem for VersionFs {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let name = match name.to_str() {
+            Some(n) => n,
+            None => return reply.error(libc::EINVAL),
+        };
+        let child = match self.nodes.get(&parent) {
+            Some(Node::Dir { children }) => children.get(name).copied(),
+            _ => None,
+        };
+        match child.and_then(|inode| self.attr_for(inode)) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, inode: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.attr_for(inode) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let version = match self.nodes.get(&inode) {
+            Some(Node::File { version }) => version.clone(),
+            _ => return reply.error(libc::ENOENT),
+        };
+
+        let start = offset as u64;
+        let end = start + size as u64;
+        let mut out = Vec::new();
+
+        for chunk in &version.chunks {
+            let chunk_start = chunk.offset;
+            let chunk_end = chunk.offset + chunk.len;
+            if chunk_end <= start || chunk_start >= end {
+                continue;
+            }
+
+            let encrypted = match self.rt.block_on(self.storage.get_block(&chunk.hash)) {
+                Ok(data) => data,
+                Err(_) => return reply.error(libc::EIO),
+            };
+            let data = match self.storage.backup_engine().decrypt_block(&encrypted, self.storage.encryption_key(), &chunk.hash)
+                .and_then(|compressed| self.storage.backup_engine().decompress(&compressed))
+            {
+                Ok(data) => data,
+                Err(_) => return reply.error(libc::EIO),
+            };
+
+            let local_start = (start.saturating_sub(chunk_start) as usize).min(data.len());
+            let local_end = ((end.min(chunk_end) - chunk_start) as usize).min(data.len());
+            out.extend_from_slice(&data[local_start..local_end]);
+        }
+
+        reply.data(&out);
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let children: Vec<(String, u64, FileType)> = match self.nodes.get(&inode) {
+            Some(Node::Dir { children }) => children
+                .iter()
+                .map(|(name, child_inode)| {
+                    let kind = match self.nodes.get(child_inode) {
+                        Some(Node::Dir { .. }) => FileType::Directory,
+                        _ => FileType::RegularFile,
+                    };
+                    (name.clone(), *child_inode, kind)
+                })
+                .collect(),
+            _ => return reply.error(libc::ENOENT),
+        };
+
+        let mut entries = vec![
+            (inode, FileType::Directory, ".".to_string()),
+            (inode, FileType::Directory, "..".to_string()),
+        ];
+        entries.extend(children.into_iter().map(|(name, ino, kind)| (ino, kind, name)));
+
+        for (idx, (ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(ino, (idx + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// A live FUSE mount. Dropping it (or calling [`MountHandle::unmount`])
+/// unmounts the filesystem.
+pub struct MountHandle {
+    session: fuser::BackgroundSession,
+}
+
+impl MountHandle {
+    pub fn unmount(self) {
+        drop(self.session);
+    }
+}
+
+/// Mount every file whose backup history includes `version` as a read-only
+/// filesystem at `mountpoint`, backed by `storage` for lazy chunk fetch.
+/// Returns immediately; the mount runs on a background thread until the
+/// returned [`MountHandle`] is dropped or explicitly unmounted. Must be
+/// called from within a Tokio runtime, since block reads on that background
+/// thread are bridged back into `storage`'s async methods.
+pub fn mount_version(
+    storage: Arc<dyn StorageBackend>,
+    backups: Vec<BackupInfo>,
+    version: u64,
+    mountpoint: &Path,
+) -> Result<MountHandle> {
+    let rt = tokio::runtime::Handle::current();
+    let fs = VersionFs::new(storage, rt, &backups, version);
+    let options = vec![MountOption::RO, MountOption::FSName("medguard".to_string())];
+    let session = fuser::spawn_mount2(fs, mountpoint, &options)
+        .with_context(|| format!("Mounting backup version {} at {}", version, mountpoint.display()))?;
+    Ok(MountHandle { session })
+}
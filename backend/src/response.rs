@@ -0,0 +1,119 @@
+//! Active-response containment: once a threat's score crosses
+//! `[response] min_score`, optionally kill the process holding the
+//! offending file open and/or cut the host's outbound network access, so
+//! exfiltration or C2 traffic stops before an operator can step in by
+//! hand. Each action is gated behind its own config flag and a global
+//! `dry_run` switch; every attempt (executed, skipped, or failed) comes
+//! back as a string for `ThreatAlert::actions_taken`.
+
+use std::path::Path;
+use std::process::Command;
+use sysinfo::{Pid, System};
+use tracing::warn;
+
+use crate::config::ResponseConfig;
+use crate::monitor::FileEvent;
+
+/// Installs the outbound block. Abstracted so a non-nftables host, or a
+/// cloud security-group backend, can be swapped in without touching the
+/// containment logic above it.
+pub trait Firewall: Send + Sync {
+    fn block_outbound(&self) -> anyhow::Result<()>;
+}
+
+/// Blocks all outbound traffic with an nftables drop policy. Requires the
+/// `nft` binary and enough privilege (`CAP_NET_ADMIN`) to run it.
+pub struct NftablesFirewall;
+
+impl Firewall for NftablesFirewall {
+    fn block_outbound(&self) -> anyhow::Result<()> {
+        let status = Command::new("nft")
+            .args(["add", "table", "inet", "medguard"])
+            .status()?;
+        anyhow::ensure!(status.success(), "nft add table failed with {}", status);
+
+        let status = Command::new("nft")
+            .args([
+                "add", "chain", "inet", "medguard", "block_outbound",
+                "{", "type", "filter", "hook", "output", "priority", "0", ";", "policy", "drop", ";", "}",
+            ])
+            .status()?;
+        anyhow::ensure!(status.success(), "nft add chain failed with {}", status);
+        Ok(())
+    }
+}
+
+/// Runs the containment actions enabled in `config` for a threat against
+/// `event`, returning a human-readable record of what was done (or, in
+/// dry-run mode, what would have been done) for audit.
+pub fn contain(event: &FileEvent, config: &ResponseConfig, firewall: &dyn Firewall) -> Vec<String> {
+    let mut actions = Vec::new();
+
+    if config.kill_process {
+        actions.push(kill_offending_process(&event.path, config.dry_run));
+    }
+
+    if config.isolate_network {
+        actions.push(isolate_network(firewall, config.dry_run));
+    }
+
+    actions
+}
+
+fn kill_offending_process(path: &Path, dry_run: bool) -> String {
+    let Some(pid) = find_owning_pid(path) else {
+        return format!("kill_process: no process found holding {} open", path.display());
+    };
+
+    if dry_run {
+        return format!("DRY RUN: would kill pid {} (holds {} open)", pid, path.display());
+    }
+
+    let mut sys = System::new_all();
+    sys.refresh_processes();
+    match sys.process(Pid::from_u32(pid)) {
+        Some(process) if process.kill() => format!("Killed pid {} (held {} open)", pid, path.display()),
+        Some(_) => format!("kill_process: signal failed for pid {}", pid),
+        None => format!("kill_process: pid {} vanished before it could be killed", pid),
+    }
+}
+
+fn isolate_network(firewall: &dyn Firewall, dry_run: bool) -> String {
+    if dry_run {
+        return "DRY RUN: would block all outbound connections".to_string();
+    }
+
+    match firewall.block_outbound() {
+        Ok(()) => "Blocked all outbound connections".to_string(),
+        Err(e) => {
+            warn!("Failed to install outbound block: {}", e);
+            format!("isolate_network: failed to install firewall rule: {}", e)
+        }
+    }
+}
+
+/// Scans `/proc/*/fd` for a symlink resolving to `path`, returning the
+/// owning PID. Best-effort: a process that already closed the file won't
+/// be found this way, and unreadable `/proc` entries (other users' procs)
+/// are skipped rather than aborting the scan.
+fn find_owning_pid(path: &Path) -> Option<u32> {
+    let target = path.canonicalize().ok()?;
+
+    for proc_entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = proc_entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        let Ok(fds) = std::fs::read_dir(proc_entry.path().join("fd")) else {
+            continue;
+        };
+
+        for fd_entry in fds.flatten() {
+            if std::fs::read_link(fd_entry.path()).ok().as_deref() == Some(target.as_path()) {
+                return Some(pid);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::Path;
+
+use crate::backup::BackupEngine;
+use crate::config::{BackupConfig, EncryptionConfig};
+use crate::crypto::Crypto;
+use crate::storage::{load_or_create_master_key, BackupInfo, StorageBackend};
+
+/// Garage/S3-compatible implementation of [`StorageBackend`]. Blocks and
+/// per-file indexes land under `blocks/<2-hex>/<hash>` and
+/// `index/<hash-of-path>.json` keys in `bucket`, so backups survive even if
+/// ransomware on the monitored host wipes the local disk. The host writes
+/// and deletes objects in the bucket itself (backups and GC), so the
+/// configured credentials need write access to it.
+pub struct S3Storage {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    backup_engine: BackupEngine,
+    crypto: Crypto,
+    encryption_key: Vec<u8>,
+    retention_versions: usize,
+    gc_lock: tokio::sync::RwLock<()>,
+}
+
+impl S3Storage {
+    pub async fn new(backup: &BackupConfig, encryption: &EncryptionConfig) -> Result<Self> {
+        let bucket = backup.s3_bucket.clone()
+            .context("backup.s3_bucket is required when backup.backend = \"s3\"")?;
+
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_config::Region::new(backup.s3_region.clone()));
+        if let Some(endpoint) = &backup.s3_endpoint {
+            loader = loader.endpoint_url(endpoint);
+        }
+        if let (Some(access_key), Some(secret_key)) = (&backup.s3_access_key_id, &backup.s3_secret_access_key) {
+            loader = loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                access_key, secret_key, None, None, "medguard-config",
+            ));
+        }
+        let config = loader.load().await;
+        let client = aws_sdk_s3::Client::new(&config);
+
+        let crypto = Crypto::new();
+        let encryption_key = load_or_create_master_key(&crypto, &encryption.master_key_path)?;
+
+        Ok(S3Storage {
+            client,
+            bucket,
+            backup_engine: BackupEngine::new(),
+            crypto,
+            encryption_key,
+            retention_versions: backup.retention_versions.max(1),
+            gc_lock: tokio::sync::RwLock::new(()),
+        })
+    }
+
+    fn block_key(&self, hash: &str) -> String {
+        let (subdir, rest) = hash.split_at(2);
+        format!("blocks/{}/{}", subdir, rest)
+    }
+
+    fn index_key(&self, file_path: &Path) -> String {
+        let hash = self.crypto.hash(file_path.to_string_lossy().as_bytes());
+        format!("index/{}.json", hash)
+    }
+
+    /// Pages through every object under `prefix`, since S3 caps a single
+    /// `ListObjectsV2` response at 1000 keys by default - returning just the
+    /// first page would silently drop anything past it, which is fatal for
+    /// GC: a block or backup-index past the first page would look
+    /// unreferenced (or disappear from the sweep) even though it's live.
+    async fn list_all_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let mut req = self.client.list_objects_v2().bucket(&self.bucket).prefix(prefix);
+            if let Some(token) = &continuation_token {
+                req = req.continuation_token(token);
+            }
+            let resp = req.send().await.with_context(|| format!("S3 list_objects_v2 ({}) failed", prefix))?;
+
+            keys.extend(resp.contents().iter().filter_map(|obj| obj.key()).map(|key| key.to_string()));
+
+            if !resp.is_truncated().unwrap_or(false) {
+                break;
+            }
+            continuation_token = resp.next_continuation_token().map(|t| t.to_string());
+        }
+
+        Ok(keys)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    async fn get_block(&self, hash: &str) -> Result<Vec<u8>> {
+        let resp = self.client.get_object()
+            .bucket(&self.bucket)
+            .key(self.block_key(hash))
+            .send()
+            .await
+            .with_context(|| format!("S3 get_object for block {}", hash))?;
+        let bytes = resp.body.collect().await.context("Reading S3 block body")?;
+        Ok(bytes.into_bytes().to_vec())
+    }
+
+    async fn put_block_if_absent(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let key = self.block_key(hash);
+        match self.client.head_object().bucket(&self.bucket).key(&key).send().await {
+            Ok(_) => return Ok(()),
+            Err(e) if e.as_service_error().map(|e| e.is_not_found()).unwrap_or(false) => {}
+            Err(e) => return Err(e).context("S3 head_object failed"),
+        }
+        self.client.put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .body(data.to_vec().into())
+            .send()
+            .await
+            .with_context(|| format!("S3 put_object for block {}", hash))?;
+        Ok(())
+    }
+
+    async fn block_size(&self, hash: &str) -> Result<u64> {
+        let resp = self.client.head_object()
+            .bucket(&self.bucket)
+            .key(self.block_key(hash))
+            .send()
+            .await
+            .with_context(|| format!("S3 head_object for block {}", hash))?;
+        Ok(resp.content_length().unwrap_or(0) as u64)
+    }
+
+    async fn list_block_hashes(&self) -> Result<Vec<String>> {
+        Ok(self.list_all_keys("blocks/").await?
+            .iter()
+            .filter_map(|key| key.strip_prefix("blocks/"))
+            .map(|rest| rest.replace('/', ""))
+            .collect())
+    }
+
+    async fn delete_block(&self, hash: &str) -> Result<()> {
+        self.client.delete_object()
+            .bucket(&self.bucket)
+            .key(self.block_key(hash))
+            .send()
+            .await
+            .with_context(|| format!("S3 delete_object for block {}", hash))?;
+        Ok(())
+    }
+
+    async fn load_backup_info(&self, file_path: &Path) -> Result<BackupInfo> {
+        let resp = self.client.get_object()
+            .bucket(&self.bucket)
+            .key(self.index_key(file_path))
+            .send()
+            .await
+            .context("S3 get_object (index) failed")?;
+        let bytes = resp.body.collect().await.context("Reading S3 index body")?;
+        Ok(serde_json::from_slice(&bytes.into_bytes())?)
+    }
+
+    async fn save_backup_info(&self, info: &BackupInfo) -> Result<()> {
+        let json = serde_json::to_vec_pretty(info)?;
+        self.client.put_object()
+            .bucket(&self.bucket)
+            .key(self.index_key(Path::new(&info.file_path)))
+            .body(json.into())
+            .send()
+            .await
+            .context("S3 put_object (index) failed")?;
+        Ok(())
+    }
+
+    async fn list_backup_infos(&self) -> Result<Vec<BackupInfo>> {
+        let keys = self.list_all_keys("index/").await?;
+
+        let mut backups = Vec::new();
+        for key in &keys {
+            let Ok(get_resp) = self.client.get_object().bucket(&self.bucket).key(key).send().await else {
+                continue;
+            };
+            let Ok(bytes) = get_resp.body.collect().await else {
+                continue;
+            };
+            if let Ok(info) = serde_json::from_slice::<BackupInfo>(&bytes.into_bytes()) {
+                backups.push(info);
+            }
+        }
+        Ok(backups)
+    }
+
+    fn backup_engine(&self) -> &BackupEngine {
+        &self.backup_engine
+    }
+
+    fn encryption_key(&self) -> &[u8] {
+        &self.encryption_key
+    }
+
+    fn retention_versions(&self) -> usize {
+        self.retention_versions
+    }
+
+    fn gc_lock(&self) -> &tokio::sync::RwLock<()> {
+        &self.gc_lock
+    }
+}
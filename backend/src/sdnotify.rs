@@ -0,0 +1,51 @@
+//! Minimal `sd_notify(3)` client: sends readiness/watchdog/status messages
+//! to the socket systemd points at via `$NOTIFY_SOCKET`, so a `Type=notify`
+//! unit can supervise MedGuard properly instead of treating "process is
+//! running" as "process is healthy".
+
+use anyhow::{Context, Result};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+/// Sends one or more `KEY=VALUE` fields as a single datagram. A no-op if
+/// `$NOTIFY_SOCKET` isn't set, i.e. we aren't running under systemd.
+fn notify(fields: &[&str]) -> Result<()> {
+    let Ok(path) = std::env::var("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+
+    let socket = UnixDatagram::unbound().context("Creating sd_notify socket")?;
+    let addr = match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name.as_bytes())
+            .context("Building abstract sd_notify socket address")?,
+        None => SocketAddr::from_pathname(&path).context("Building sd_notify socket address")?,
+    };
+    socket
+        .send_to_addr(fields.join("\n").as_bytes(), &addr)
+        .context("Sending sd_notify message")?;
+    Ok(())
+}
+
+pub fn notify_ready() -> Result<()> {
+    notify(&["READY=1"])
+}
+
+pub fn notify_stopping() -> Result<()> {
+    notify(&["STOPPING=1"])
+}
+
+/// Sends a watchdog ping and a human-readable status line (mirroring the
+/// `/api/status` fields) in a single datagram.
+pub fn notify_watchdog_status(status: &str) -> Result<()> {
+    let status_field = format!("STATUS={}", status);
+    notify(&["WATCHDOG=1", status_field.as_str()])
+}
+
+/// Half of `$WATCHDOG_USEC` if systemd configured a watchdog for this unit
+/// (pinging at the full interval risks a ping landing right after the
+/// deadline under load), or `None` if no watchdog is configured.
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec) / 2)
+}
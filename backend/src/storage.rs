@@ -1,11 +1,18 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::debug;
 
 use crate::backup::{BackupEngine, BackupVersion};
-use crate::crypto::Crypto;
+use crate::config::{BackupConfig, EncryptionConfig};
+use crate::crypto::{Crypto, WrappedKey};
+
+/// Environment variable holding the passphrase that unlocks (or creates)
+/// the passphrase-wrapped master key at `EncryptionConfig::master_key_path`.
+const PASSPHRASE_ENV_VAR: &str = "MEDGUARD_PASSPHRASE";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupInfo {
@@ -13,48 +20,132 @@ pub struct BackupInfo {
     pub versions: Vec<BackupVersion>,
 }
 
-pub struct Storage {
-    storage_path: PathBuf,
-    backup_engine: BackupEngine,
-    crypto: Crypto,
-    encryption_key: Vec<u8>,
+/// Result of a [`gc_unreferenced_blocks`] pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcStats {
+    pub blocks_removed: usize,
+    pub bytes_freed: u64,
 }
 
-impl Storage {
-    pub fn new(storage_path: &str) -> Result<Self> {
-        let path = PathBuf::from(storage_path);
-        std::fs::create_dir_all(&path)?;
+/// Result of a [`repo_stats`] pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoStats {
+    /// Sum of every retained version's `FileMetadata::size`, i.e. what
+    /// restoring everything at once would cost with no dedup at all.
+    pub logical_bytes: u64,
+    /// Sum of the actual on-disk/on-bucket size of every unique block.
+    pub physical_bytes: u64,
+    /// `logical_bytes / physical_bytes`; `1.0` means dedup bought nothing,
+    /// higher is better.
+    pub dedup_ratio: f64,
+    pub unique_blocks: usize,
+    /// `file_path -> retained version count`.
+    pub file_version_counts: HashMap<String, usize>,
+}
 
-        let crypto = Crypto::new();
-        let encryption_key = crypto.generate_key()?;
+/// Result of a [`verify_blocks`] scrub pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub blocks_checked: usize,
+    /// Blocks that exist but whose content doesn't decrypt/decompress into
+    /// data matching their own hash.
+    pub corrupt_blocks: Vec<String>,
+    /// Blocks a backup-info version references that aren't in the store.
+    pub missing_blocks: Vec<String>,
+}
 
-        Ok(Storage {
-            storage_path: path,
-            backup_engine: BackupEngine::new(4096),
-            crypto,
-            encryption_key,
-        })
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupt_blocks.is_empty() && self.missing_blocks.is_empty()
+    }
+}
+
+/// Unlock the master key wrapped at `master_key_path`, or generate a new
+/// one and wrap+persist it if no wrapped key exists yet. Either way, a
+/// passphrase is required via `MEDGUARD_PASSPHRASE` so the key material on
+/// disk is useless without it. Shared by every [`StorageBackend`] impl,
+/// since key management doesn't depend on where blocks end up living.
+pub(crate) fn load_or_create_master_key(crypto: &Crypto, master_key_path: &str) -> Result<Vec<u8>> {
+    let passphrase = std::env::var(PASSPHRASE_ENV_VAR)
+        .with_context(|| format!("{} must be set to unlock the master key", PASSPHRASE_ENV_VAR))?;
+    let path = Path::new(master_key_path);
+
+    if path.exists() {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading wrapped master key at {}", path.display()))?;
+        let wrapped: WrappedKey = serde_json::from_str(&content)?;
+        crypto.unwrap_master_key(&wrapped, &passphrase)
+            .context("Failed to unlock master key (wrong passphrase?)")
+    } else {
+        let master_key = crypto.generate_key()?;
+        let wrapped = crypto.wrap_master_key(&master_key, &passphrase)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&wrapped)?)
+            .with_context(|| format!("Writing wrapped master key to {}", path.display()))?;
+        Ok(master_key)
     }
+}
 
-    pub async fn backup_file(&self, file_path: &Path) -> Result<()> {
+/// Storage backend for encrypted, content-addressed backup blocks and their
+/// per-file version indexes. `backup_file`/`restore_file`/`list_backups`/
+/// `backup_count` are provided methods built on top of the lower-level
+/// block/index primitives each impl supplies, so a new backend (see
+/// [`crate::s3_storage::S3Storage`]) never means re-implementing chunking,
+/// compression or encryption - only where the bytes land.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    async fn get_block(&self, hash: &str) -> Result<Vec<u8>>;
+    /// Writes `data` under `hash` unless a block with that hash already
+    /// exists, preserving the store's write-once dedup invariant.
+    async fn put_block_if_absent(&self, hash: &str, data: &[u8]) -> Result<()>;
+    async fn block_size(&self, hash: &str) -> Result<u64>;
+    /// All block hashes currently in the store, for GC mark-and-sweep.
+    async fn list_block_hashes(&self) -> Result<Vec<String>>;
+    async fn delete_block(&self, hash: &str) -> Result<()>;
+
+    async fn load_backup_info(&self, file_path: &Path) -> Result<BackupInfo>;
+    async fn save_backup_info(&self, info: &BackupInfo) -> Result<()>;
+    async fn list_backup_infos(&self) -> Result<Vec<BackupInfo>>;
+
+    fn backup_engine(&self) -> &BackupEngine;
+    fn encryption_key(&self) -> &[u8];
+    fn retention_versions(&self) -> usize;
+    /// Serializes the write-then-reference-it sequence in `backup_file`
+    /// (`put_block_if_absent` followed eventually by `save_backup_info`)
+    /// against [`gc_unreferenced_blocks`]: concurrent `backup_file` calls
+    /// only need a shared read lock and can still run in parallel, but GC
+    /// takes the exclusive write lock so it never observes a block that's
+    /// been written but not yet referenced by a saved backup-info version.
+    fn gc_lock(&self) -> &tokio::sync::RwLock<()>;
+
+    async fn backup_file(&self, file_path: &Path) -> Result<()> {
         if !file_path.exists() {
             return Ok(()); // File deleted, skip backup
         }
 
-        // Create backup version
-        let backup_version = self.backup_engine.create_backup(&file_path.to_path_buf())?;
-
-        // Create storage path for this file
-        let storage_file_path = self.get_storage_path(file_path);
-        fs::create_dir_all(storage_file_path.parent().unwrap()).await?;
-
-        // Load existing backup info or create new
         let mut backup_info = self.load_backup_info(file_path).await
             .unwrap_or_else(|_| BackupInfo {
                 file_path: file_path.to_string_lossy().to_string(),
                 versions: Vec::new(),
             });
 
+        // Chunk, compress and encrypt synchronously - that's CPU-only work.
+        // The actual block writes happen in the async loop below so a
+        // backend that has to talk to the network (e.g. S3) isn't forced to
+        // block inside the chunker's callback.
+        let reader = std::fs::File::open(file_path)
+            .context(format!("Failed to open file: {:?}", file_path))?;
+        let metadata = self.backup_engine().file_metadata(file_path)?;
+        let mut pending_blocks: Vec<(String, Vec<u8>)> = Vec::new();
+        let backup_version = self.backup_engine().backup_reader(reader, metadata, |chunk, data| {
+            let compressed = self.backup_engine().compress(data)?;
+            let encrypted = self.backup_engine().encrypt_block(&compressed, self.encryption_key(), &chunk.hash)?;
+            pending_blocks.push((chunk.hash.clone(), encrypted));
+            Ok(())
+        })?;
+
         // Check if file actually changed
         if let Some(last_version) = backup_info.versions.last() {
             if last_version.file_hash == backup_version.file_hash {
@@ -63,42 +154,48 @@ impl Storage {
             }
         }
 
-        // Store blocks
-        let file_data = fs::read(file_path).await?;
-        for (idx, chunk) in file_data.chunks(4096).enumerate() {
-            let block_hash = &backup_version.block_hashes[idx];
-            let block_path = self.get_block_path(block_hash);
-
-            // Only store if block doesn't exist (deduplication)
-            if !block_path.exists() {
-                // Compress and encrypt block
-                let compressed = self.backup_engine.compress(chunk)?;
-                let encrypted = self.backup_engine.encrypt_block(&compressed, &self.encryption_key)?;
-                
-                fs::create_dir_all(block_path.parent().unwrap()).await?;
-                fs::write(&block_path, encrypted).await?;
-            }
+        // Hold the shared GC lock across the write-then-reference sequence
+        // below: a block written by `put_block_if_absent` isn't referenced
+        // by anything on disk until `save_backup_info` persists the version
+        // pointing at it, so a GC pass running in that window would see the
+        // block as unreferenced and delete it out from under this backup.
+        let gc_guard = self.gc_lock().read().await;
+
+        for (hash, encrypted) in &pending_blocks {
+            self.put_block_if_absent(hash, encrypted).await?;
         }
 
         // Add version and save backup info
         let version_number = backup_info.versions.len() as u64 + 1;
         let mut new_version = backup_version;
         new_version.version = version_number;
-        
+
         backup_info.versions.push(new_version);
 
-        // Keep only last N versions
-        if backup_info.versions.len() > 100 {
-            backup_info.versions.drain(0..10); // Remove oldest 10
+        // Keep only `retention_versions` versions. Dropping older ones can
+        // orphan blocks that only they referenced, so follow up with a GC
+        // pass over the whole chunk store (other files may still reference
+        // the same block, which is why this isn't a simple per-file delete).
+        let trimmed = backup_info.versions.len() > self.retention_versions();
+        if trimmed {
+            let excess = backup_info.versions.len() - self.retention_versions();
+            backup_info.versions.drain(0..excess);
+        }
+
+        self.save_backup_info(&backup_info).await?;
+        drop(gc_guard);
+
+        if trimmed {
+            if let Err(e) = gc_unreferenced_blocks(self).await {
+                debug!("Chunk GC pass failed: {}", e);
+            }
         }
 
-        self.save_backup_info(file_path, &backup_info).await?;
-        
         debug!("Backed up {:?} - version {}", file_path, version_number);
         Ok(())
     }
 
-    pub async fn restore_file(&self, file_path: &str, version: Option<u64>) -> Result<()> {
+    async fn restore_file(&self, file_path: &str, version: Option<u64>) -> Result<()> {
         let path = PathBuf::from(file_path);
         let backup_info = self.load_backup_info(&path).await?;
 
@@ -117,29 +214,265 @@ impl Storage {
 
         // Reconstruct file from blocks
         let mut file_data = Vec::new();
-        
+
         for block_hash in &backup_version.block_hashes {
-            let block_path = self.get_block_path(block_hash);
-            let encrypted_block = fs::read(&block_path).await
+            let encrypted_block = self.get_block(block_hash).await
                 .context(format!("Block not found: {}", block_hash))?;
-            
+
             // Decrypt and decompress
-            let compressed = self.backup_engine.decrypt_block(&encrypted_block, &self.encryption_key)?;
-            let block_data = self.backup_engine.decompress(&compressed)?;
-            
+            let compressed = self.backup_engine().decrypt_block(&encrypted_block, self.encryption_key(), block_hash)?;
+            let block_data = self.backup_engine().decompress(&compressed)?;
+
             file_data.extend_from_slice(&block_data);
         }
 
         // Write restored file
         fs::write(&path, file_data).await?;
-        
+
         debug!("Restored {:?} from version {}", path, backup_version.version);
         Ok(())
     }
 
-    pub async fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+    async fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        self.list_backup_infos().await
+    }
+
+    async fn backup_count(&self) -> Result<usize> {
+        let backups = self.list_backups().await?;
+        let total: usize = backups.iter().map(|b| b.versions.len()).sum();
+        Ok(total)
+    }
+}
+
+/// Scan every backup's retained versions to build the set of block hashes
+/// still referenced anywhere in the store, then delete any block that isn't
+/// in that set. Safe to run while backups are in progress elsewhere: it
+/// takes `storage.gc_lock()`'s exclusive write lock for the whole pass,
+/// which blocks until every `backup_file` currently between writing its
+/// blocks and saving the version that references them (each holding the
+/// lock's shared read side) has finished, so GC never sees a written block
+/// as unreferenced just because its backup-info save hasn't landed yet.
+///
+/// Takes `&dyn StorageBackend` rather than being a trait method itself,
+/// since it isn't part of the public backend contract - it's the internal
+/// maintenance pass `backup_file` triggers after trimming old versions.
+pub async fn gc_unreferenced_blocks(storage: &dyn StorageBackend) -> Result<GcStats> {
+    let _gc_guard = storage.gc_lock().write().await;
+    let backups = storage.list_backup_infos().await?;
+    let mut referenced: HashSet<String> = HashSet::new();
+    for info in &backups {
+        for version in &info.versions {
+            referenced.extend(version.block_hashes.iter().cloned());
+        }
+    }
+
+    let mut blocks_removed = 0;
+    let mut bytes_freed = 0u64;
+    for hash in storage.list_block_hashes().await? {
+        if referenced.contains(&hash) {
+            continue;
+        }
+        let len = storage.block_size(&hash).await.unwrap_or(0);
+        storage.delete_block(&hash).await?;
+        blocks_removed += 1;
+        bytes_freed += len;
+    }
+
+    debug!("GC removed {} blocks ({} bytes)", blocks_removed, bytes_freed);
+    Ok(GcStats { blocks_removed, bytes_freed })
+}
+
+/// Reports how much space dedup is actually saving: logical size (every
+/// retained version, as if none of it were deduplicated) against physical
+/// size (the unique blocks actually stored), plus per-file version counts.
+///
+/// Takes `&dyn StorageBackend` for the same reason [`gc_unreferenced_blocks`]
+/// does - this is an operator/CLI concern, not part of the storage contract.
+pub async fn repo_stats(storage: &dyn StorageBackend) -> Result<RepoStats> {
+    let backups = storage.list_backup_infos().await?;
+
+    let mut logical_bytes = 0u64;
+    let mut file_version_counts = HashMap::new();
+    for info in &backups {
+        file_version_counts.insert(info.file_path.clone(), info.versions.len());
+        logical_bytes += info.versions.iter().map(|v| v.metadata.size).sum::<u64>();
+    }
+
+    let block_hashes = storage.list_block_hashes().await?;
+    let mut physical_bytes = 0u64;
+    for hash in &block_hashes {
+        physical_bytes += storage.block_size(hash).await.unwrap_or(0);
+    }
+
+    let dedup_ratio = if physical_bytes == 0 {
+        1.0
+    } else {
+        logical_bytes as f64 / physical_bytes as f64
+    };
+
+    Ok(RepoStats {
+        logical_bytes,
+        physical_bytes,
+        dedup_ratio,
+        unique_blocks: block_hashes.len(),
+        file_version_counts,
+    })
+}
+
+/// Scrubs the store: re-fetches every block, decrypts and decompresses it,
+/// and checks the result still hashes to the block's own name; separately
+/// checks that every block a retained version references actually exists.
+/// Doesn't touch anything - purely a read/report pass for operator
+/// confidence that a restore will actually succeed.
+pub async fn verify_blocks(storage: &dyn StorageBackend) -> Result<VerifyReport> {
+    let block_hashes = storage.list_block_hashes().await?;
+    let stored: HashSet<String> = block_hashes.iter().cloned().collect();
+
+    let mut corrupt_blocks = Vec::new();
+    for hash in &block_hashes {
+        let intact = match storage.get_block(hash).await {
+            Ok(encrypted) => storage
+                .backup_engine()
+                .decrypt_block(&encrypted, storage.encryption_key(), hash)
+                .and_then(|compressed| storage.backup_engine().decompress(&compressed))
+                .map(|data| blake3::hash(&data).to_hex().to_string() == *hash)
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        if !intact {
+            corrupt_blocks.push(hash.clone());
+        }
+    }
+
+    let mut referenced: HashSet<String> = HashSet::new();
+    for info in storage.list_backup_infos().await? {
+        for version in info.versions {
+            referenced.extend(version.block_hashes);
+        }
+    }
+    let missing_blocks: Vec<String> = referenced.difference(&stored).cloned().collect();
+
+    Ok(VerifyReport {
+        blocks_checked: block_hashes.len(),
+        corrupt_blocks,
+        missing_blocks,
+    })
+}
+
+/// Local filesystem implementation of [`StorageBackend`]: blocks live under
+/// `storage_path/blocks/<2-hex>/<hash>` and per-file indexes are
+/// `storage_path/<hash-of-path>.json`.
+pub struct LocalStorage {
+    storage_path: PathBuf,
+    backup_engine: BackupEngine,
+    crypto: Crypto,
+    encryption_key: Vec<u8>,
+    retention_versions: usize,
+    gc_lock: tokio::sync::RwLock<()>,
+}
+
+impl LocalStorage {
+    pub fn new(storage_path: &str, encryption: &EncryptionConfig, backup: &BackupConfig) -> Result<Self> {
+        let path = PathBuf::from(storage_path);
+        std::fs::create_dir_all(&path)?;
+
+        let crypto = Crypto::new();
+        let encryption_key = load_or_create_master_key(&crypto, &encryption.master_key_path)?;
+
+        Ok(LocalStorage {
+            storage_path: path,
+            backup_engine: BackupEngine::new(),
+            crypto,
+            encryption_key,
+            retention_versions: backup.retention_versions.max(1),
+            gc_lock: tokio::sync::RwLock::new(()),
+        })
+    }
+
+    fn index_path(&self, file_path: &Path) -> PathBuf {
+        let hash = self.crypto.hash(file_path.to_string_lossy().as_bytes());
+        self.storage_path.join(format!("{}.json", hash))
+    }
+
+    fn block_path(&self, block_hash: &str) -> PathBuf {
+        // Use first 2 chars as subdirectory for better file system performance
+        let subdir = &block_hash[..2];
+        self.storage_path.join("blocks").join(subdir).join(block_hash)
+    }
+
+    /// Read, decrypt and decompress a single block by hash, straight from
+    /// the local block directory.
+    pub(crate) fn read_block(&self, block_hash: &str) -> Result<Vec<u8>> {
+        let encrypted = std::fs::read(self.block_path(block_hash))
+            .with_context(|| format!("Block not found: {}", block_hash))?;
+        let compressed = self.backup_engine.decrypt_block(&encrypted, &self.encryption_key, block_hash)?;
+        self.backup_engine.decompress(&compressed)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    async fn get_block(&self, hash: &str) -> Result<Vec<u8>> {
+        fs::read(self.block_path(hash)).await
+            .with_context(|| format!("Block not found: {}", hash))
+    }
+
+    async fn put_block_if_absent(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let block_path = self.block_path(hash);
+        if block_path.exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(block_path.parent().unwrap()).await?;
+        fs::write(&block_path, data).await?;
+        Ok(())
+    }
+
+    async fn block_size(&self, hash: &str) -> Result<u64> {
+        Ok(fs::metadata(self.block_path(hash)).await?.len())
+    }
+
+    async fn list_block_hashes(&self) -> Result<Vec<String>> {
+        let blocks_dir = self.storage_path.join("blocks");
+        let mut hashes = Vec::new();
+        if !blocks_dir.exists() {
+            return Ok(hashes);
+        }
+
+        let mut subdirs = fs::read_dir(&blocks_dir).await?;
+        while let Some(subdir) = subdirs.next_entry().await? {
+            if !subdir.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut entries = fs::read_dir(subdir.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                hashes.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        Ok(hashes)
+    }
+
+    async fn delete_block(&self, hash: &str) -> Result<()> {
+        fs::remove_file(self.block_path(hash)).await?;
+        Ok(())
+    }
+
+    async fn load_backup_info(&self, file_path: &Path) -> Result<BackupInfo> {
+        let content = fs::read_to_string(self.index_path(file_path)).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    async fn save_backup_info(&self, info: &BackupInfo) -> Result<()> {
+        let path = self.index_path(Path::new(&info.file_path));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, serde_json::to_string_pretty(info)?).await?;
+        Ok(())
+    }
+
+    async fn list_backup_infos(&self) -> Result<Vec<BackupInfo>> {
         let mut backups = Vec::new();
-        
+
         let mut entries = fs::read_dir(&self.storage_path).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -155,34 +488,19 @@ impl Storage {
         Ok(backups)
     }
 
-    pub async fn backup_count(&self) -> Result<usize> {
-        let backups = self.list_backups().await?;
-        let total: usize = backups.iter().map(|b| b.versions.len()).sum();
-        Ok(total)
+    fn backup_engine(&self) -> &BackupEngine {
+        &self.backup_engine
     }
 
-    fn get_storage_path(&self, file_path: &Path) -> PathBuf {
-        let hash = self.crypto.hash(file_path.to_string_lossy().as_bytes());
-        self.storage_path.join(format!("{}.json", hash))
-    }
-
-    fn get_block_path(&self, block_hash: &str) -> PathBuf {
-        // Use first 2 chars as subdirectory for better file system performance
-        let subdir = &block_hash[..2];
-        self.storage_path.join("blocks").join(subdir).join(block_hash)
+    fn encryption_key(&self) -> &[u8] {
+        &self.encryption_key
     }
 
-    async fn load_backup_info(&self, file_path: &Path) -> Result<BackupInfo> {
-        let storage_path = self.get_storage_path(file_path);
-        let content = fs::read_to_string(storage_path).await?;
-        let info = serde_json::from_str(&content)?;
-        Ok(info)
+    fn retention_versions(&self) -> usize {
+        self.retention_versions
     }
 
-    async fn save_backup_info(&self, file_path: &Path, info: &BackupInfo) -> Result<()> {
-        let storage_path = self.get_storage_path(file_path);
-        let content = serde_json::to_string_pretty(info)?;
-        fs::write(storage_path, content).await?;
-        Ok(())
+    fn gc_lock(&self) -> &tokio::sync::RwLock<()> {
+        &self.gc_lock
     }
 }
@@ -0,0 +1,338 @@
+/*
+ * 콘텐츠 기반 청크 저장소 (Content-Defined Chunk Store)
+ *
+ * IncrementalBackupEngine이 변경된 파일 전체를 복사하는 대신, 파일을
+ * FastCDC로 가변 길이 청크로 나누고 blake3 해시로 청크 스토어에 한 번만
+ * 저장한다. 같은 내용의 청크는 여러 파일/백업에 걸쳐 한 번만 디스크에
+ * 존재하므로, 큰 의료 영상/DB 파일의 일부만 바뀌어도 I/O와 저장 공간을
+ * 크게 아낀다.
+ */
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// 청크 경계를 강제하는 최소 크기.
+const MIN_SIZE: usize = 2 * 1024;
+/// 정규화 마스크가 전환되는 목표 평균 크기.
+const AVG_SIZE: usize = 8 * 1024;
+/// 지문이 맞지 않아도 여기서 강제로 자르는 최대 크기.
+const MAX_SIZE: usize = 64 * 1024;
+
+/// `AVG_SIZE` 이전에 쓰는 마스크 (1비트가 많아 적중 확률이 낮음 -> 너무 일찍 안 잘림).
+const MASK_SMALL: u64 = (1u64 << 15) - 1;
+/// `AVG_SIZE` 이후에 쓰는 마스크 (1비트가 적어 적중 확률이 높음 -> 목표 크기로 수렴).
+const MASK_LARGE: u64 = (1u64 << 11) - 1;
+
+/// FastCDC 지문을 굴리는 데 쓰는 난수 테이블. 같은 내용은 항상 같은 위치에서
+/// 잘리도록 고정되어 있다.
+const GEAR: [u64; 256] = [
+    0x56cebcb42e44ceff, 0x3dd36a279f7885fd, 0xe0b55bcfb1452e8b, 0xfdcb6e49d3f8e05e,
+    0x2961904df2d818d7, 0xfeeb7f263f9de792, 0xaea89e9189ff867d, 0x6b3604834d442f78,
+    0xf185e7bfdb18d168, 0x29475d911b093eea, 0xcd50a22816bca01e, 0x09e1f6d993d85e51,
+    0x19eefe41eec5c084, 0x72aab8ae59f88715, 0x977c79bfc929f384, 0x2b00ed39bb9f1adb,
+    0x182df2a0dc9cfbe7, 0x60ac1c192df332ba, 0x40434516e7edbaeb, 0x4853c377ea78db78,
+    0xbb930f65cff7b8e4, 0x214e4516f76e1ae9, 0xd04d4a7b017f4013, 0x6c01579c1245ccd9,
+    0x7b21eaf7e26f8ee7, 0x99646e13e5f28316, 0xe2151896fc9ad5a3, 0xc5546af126d99b82,
+    0x0f339486ce4c1580, 0x5a163a6be90a803e, 0x992c7cdc8b5606fd, 0x9500b9446a558d70,
+    0xb9dee21997f1a81e, 0xbc0ffd3b32e36d06, 0xfcc96bb9c081d707, 0xf6ecb9627603856a,
+    0xa0d0a90cab6d2636, 0x6e915f58bd9f7485, 0x4d9c857bba7f1fa0, 0x22d74b853b3b4db9,
+    0x6d550aec14a5fd75, 0xeb097df9acdb5faf, 0xd4fb3472fdb1e0a0, 0xe25aa87dfce1b123,
+    0x37318cfb25f96081, 0x7972fcb67949aa55, 0xc042c125993e860c, 0x84a1b613e62bae8b,
+    0x98962784cbfb772e, 0x57b75d763bf77fd2, 0xc4745669af783630, 0xe544490b631b9be2,
+    0x26b7bbb61521520c, 0x0d2a6f4d6e514edc, 0x030e8b4f32f02fe7, 0xbe7eee54d9792638,
+    0x88d0ac3cdb4e59db, 0xeb0551b6c6c95547, 0x3f319251d96876a8, 0xb99ed66ff208761f,
+    0x425293584aa03e06, 0xb1bda70e9e7975a6, 0xa582fe36764ba3c7, 0x3c15a0c15a9a81d5,
+    0x0fed8ff11c3a28ad, 0x39616017a2258e85, 0xb042ce97bf5d355c, 0x68b134555ee441b2,
+    0xa37828f76368a532, 0x71c075d6883ae835, 0x21087e675d8f851d, 0xea8dd55a2c262eb0,
+    0x9cce434f5c2d0a56, 0x0c5633702c0f66f9, 0x80f3ebec2aaab9e7, 0x8d1796900b348fe1,
+    0x354bcbaf94b581e4, 0x5ab1d48ee1f27254, 0xfe5cf32e379e5cc7, 0xd2045e6c7842fefb,
+    0x60ffacd2061968a7, 0xd341e1bed460b038, 0x2578fa067f35e654, 0xff1a54b4f654f439,
+    0x4e9e75e4216e6ae9, 0xc4e9dca2d4b4aef3, 0xe1a7966954f3c699, 0x2890269e3dae6d64,
+    0x54df8a0653975660, 0x184a82310b3e3622, 0x99e8239922c3d443, 0x57e2ef255dfddc7a,
+    0x4e9cce9f0d6da1e4, 0x37116d770942a667, 0xd69d3c30bb4b4ce3, 0x9b3934057acdead2,
+    0x31ef1063070a7dc6, 0x5e0d0cb3c788400a, 0xcee35ef199992e62, 0x8b032a7f3dff389d,
+    0x970313cfb748979f, 0x841a9122e25729cb, 0xa43a894469ebe2be, 0xfa111c33e16c2a13,
+    0x64f017ed6c16defd, 0x52e1479b917e7bad, 0xa76922fcc7bf1655, 0x02f96f651e3edfb7,
+    0x99a309303453802d, 0x23e48d53be304790, 0x7457d71fe0406a93, 0x14ea71cc5a355781,
+    0x91213b10783c13a0, 0xadec66f2e21e9719, 0x70e74b6b840d2da3, 0x1683b762665e7e9d,
+    0x80fbf6430e4cba84, 0x7f982799f307a296, 0x4bbb77392d352b52, 0xba1d2283266a7cb1,
+    0x64607230c56d853e, 0x6f23b1ceb5c2a97e, 0x667fff67e4b30fde, 0x86c11a93cd2fc86c,
+    0x3097c050442e5aea, 0x61a243f376b7c9a2, 0x3a577397324a3190, 0x5a90182e479f2ea8,
+    0xc81fcd46a46da7fd, 0xcd88272dbdce4bf2, 0x956727cb078c697f, 0xcba27038b1b4de01,
+    0x76eea3c509aff69f, 0x8dc16536f1c98068, 0xf1e5b24b80fcb7da, 0xea4560de4dfffbd8,
+    0x78be2363a0eb3e5c, 0x480ff2d63182265f, 0x5f51e75d1918ed38, 0x37c0529ae5e23240,
+    0x40213d827d38d07d, 0xfb65ee0651d68424, 0xd33dc66c32c6cd9d, 0x6e51d55c546ff8d7,
+    0x6c66789d3826b1a9, 0xf85f80a3fc745aa7, 0xdc0c1c0118babc0f, 0x3219e51f5728de65,
+    0x17c4d49cb67e3719, 0x4c40f231b379fe79, 0x5d4883e493af616f, 0xaa8de98c0f3da67c,
+    0xdd5f7bcfde5d947f, 0x8f9e22986eec5a1b, 0x0ca23a01f3cd121e, 0x4c02c7abcfd7ad6a,
+    0xf3c904fdd0ca90bf, 0x9213c51a48065032, 0x5a5100b2db8b15ff, 0x5bc651690f513ca2,
+    0x14f28de07eeccc75, 0x72c2a888cc325206, 0xb5fce19c5fc01d8f, 0xc64a9567bb58de55,
+    0x9204c35dc6ff5bc2, 0x65959b7cc3d3f101, 0x3a36e398e6ed3257, 0x2445911303bfcde1,
+    0x2cdf2e96ad612b4b, 0x0a37de10952d3960, 0xf4511ef85802bf57, 0xab42ce36d4132b52,
+    0x17d5a54035e59a55, 0xeb002d8c0da1b4d9, 0x89838247cba78082, 0x825ded70f26fb27b,
+    0x88593a09f7869890, 0xc3b45e3fd38d5f08, 0xc75c9cfb19501839, 0x9e5b14c6e3b28547,
+    0xc3f7d6d706305af6, 0xef76b7f6b3c3c26c, 0xa3e687f8b0e58f1d, 0x8f2abc35ab8f2b11,
+    0xb2e23ea76d74dd74, 0xdc4e4b3757bf6883, 0x23c36aa556f0fd19, 0x8cc206be069e9a41,
+    0xc023c4d01676bffb, 0xd32b793fb32e8d0a, 0x2a0ecc3f616a83d0, 0x1aae0c6e981c29ee,
+    0x97897c680e5a2098, 0x2601f6d3554f9d44, 0x43c0d306eb551ccc, 0x641c8725f2ed73ff,
+    0x7f2b1e342e27cc16, 0xd69edfcb4ae2a196, 0x1c37ca571f79e6c8, 0x4346b6653b5e33fc,
+    0xba01bbfa6fe9aef8, 0xdcfdc1fcd77e4728, 0xd829e16db6e795b3, 0xe062ca3c03a4452f,
+    0x68a16b1a33f9309f, 0x7bebbed2700d23f3, 0x59a63e3895ddd696, 0x5a4609527c17ec21,
+    0x377feb84f44c6067, 0x410561f882a107cf, 0xda93aff896214164, 0x907f9c5579c54223,
+    0x59294976c0f07aa0, 0x23176bea0507b479, 0x3f189cd18a2dafee, 0xbbe886ff292683ec,
+    0xed2898c44520f54c, 0xc12f924335050cda, 0xb6ed88c5659762ce, 0xa4e67256415eded5,
+    0xbfb6776a21a728f7, 0xb4f8d17d114550b0, 0x1dbeef77a8b7433f, 0xc7d919f98964c356,
+    0xa6a101c1bdb5d249, 0x20bed3b4e1faba48, 0x9be8150e31ecc303, 0xa21b51c3d944d811,
+    0xbe7e0982bd8691be, 0x788b54a539ccf4f6, 0x15f8a7b84b302c21, 0x53265347573f0e5c,
+    0x7743bda0e49faea6, 0xe2cbad2a86de6f2a, 0x8c0a5f567b8989b4, 0xbfc6cc9f29679129,
+    0x02eed93d48a84ee3, 0x55f40abb999e3f75, 0x3e1cacfbb3e68635, 0x7af80a6ec9c42eb3,
+    0x5bf20e939bb8d296, 0xa35642dcf2f91832, 0xbe799c78a4fad26d, 0x657a73bf6fca938c,
+    0x85ff99c1f1977129, 0x118740b05c842eba, 0x6054697e65b74fec, 0x824529a087fd4948,
+    0x26a89d2343f7aac7, 0xf255168be02082d0, 0x56641d4f8df41eaf, 0x8223de9e5a6edbf4,
+    0x35d6d011129cb35a, 0xaed1090e2eac3be6, 0x33bd4dea836be632, 0xd2088c1227a998d7,
+];
+
+/// 청크 하나에 대한 참조 (매니페스트에 순서대로 저장됨).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRef {
+    pub hash: String,
+    pub len: u64,
+}
+
+/// 한 번의 백업에 대한 매니페스트: 상대 경로별로 순서가 있는 청크 해시 목록.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub timestamp: u64,
+    pub files: HashMap<String, Vec<ChunkRef>>,
+}
+
+/// 청크 하나를 쓰는 동안 경쟁하는 다른 스레드들이 결과를 기다리는 곳.
+/// `Option`이 `Some`이 되는 순간이 쓰기가 끝난 시점이고, 값은 그 쓰기가
+/// 성공했는지를 담는다.
+type ChunkWriteOutcome = Arc<(Mutex<Option<bool>>, Condvar)>;
+
+/// 청크를 `backup_dir/chunks/<hash>`에 콘텐츠 주소 방식으로 저장하는 저장소.
+/// 같은 해시의 청크는 한 번만 디스크에 쓰인다.
+pub struct ChunkStore {
+    chunks_dir: PathBuf,
+    // 같은 청크를 동시에 두 스레드가 쓰려고 할 때 한쪽만 실제로 쓰게 하고,
+    // 진 쪽은 여기서 이긴 쪽의 결과를 기다린다.
+    in_flight: Mutex<HashMap<String, ChunkWriteOutcome>>,
+}
+
+impl ChunkStore {
+    pub fn new(backup_dir: &Path) -> Self {
+        Self {
+            chunks_dir: backup_dir.join("chunks"),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `data`를 FastCDC로 청크로 나누고, 아직 저장소에 없는 청크만 디스크에
+    /// 쓴 뒤 순서가 있는 `ChunkRef` 목록을 반환한다.
+    pub fn split_and_store(&self, data: &[u8]) -> Result<Vec<ChunkRef>> {
+        fs::create_dir_all(&self.chunks_dir)?;
+
+        let mut refs = Vec::new();
+        let mut start = 0usize;
+
+        while start < data.len() {
+            let mut fp: u64 = 0;
+            let mut pos = start;
+
+            while pos < data.len() {
+                fp = (fp << 1).wrapping_add(GEAR[data[pos] as usize]);
+                pos += 1;
+                let len = pos - start;
+
+                if len >= MAX_SIZE {
+                    break;
+                }
+                if len < MIN_SIZE {
+                    continue;
+                }
+
+                let mask = if len < AVG_SIZE { MASK_SMALL } else { MASK_LARGE };
+                if fp & mask == 0 {
+                    break;
+                }
+            }
+
+            let slice = &data[start..pos];
+            let hash = blake3::hash(slice).to_hex().to_string();
+            self.store_chunk(&hash, slice)?;
+            refs.push(ChunkRef { hash, len: slice.len() as u64 });
+            start = pos;
+        }
+
+        Ok(refs)
+    }
+
+    /// 이 해시의 청크가 저장소에 없으면 쓴다. 동시에 같은 해시를 쓰려는
+    /// 스레드가 있으면, `in_flight`에 먼저 등록한 쪽만 실제로 쓰고, 진 쪽은
+    /// 이긴 쪽의 쓰기가 끝날 때까지 기다렸다가 실제로 파일이 생겼는지
+    /// 확인한 뒤에만 성공을 보고한다 (이긴 쪽의 쓰기가 디스크 공간 부족 등
+    /// 으로 실패했는데도 진 쪽이 무작정 성공을 보고하면, 매니페스트가 한
+    /// 번도 쓰인 적 없는 청크를 가리키게 된다).
+    fn store_chunk(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let path = self.chunk_path(hash);
+        if path.exists() {
+            return Ok(());
+        }
+
+        let existing = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(hash) {
+                Some(outcome) => Some(outcome.clone()),
+                None => {
+                    in_flight.insert(hash.to_string(), Arc::new((Mutex::new(None), Condvar::new())));
+                    None
+                }
+            }
+        };
+
+        let Some(outcome) = existing else {
+            // 우리가 이겼다: 실제로 쓰고 결과를 기다리던 쪽들에게 알린다.
+            let result = fs::write(&path, data).with_context(|| format!("Writing chunk {}", hash));
+
+            let outcome = self.in_flight.lock().unwrap().remove(hash).expect("we just inserted this entry");
+            let (state, cvar) = &*outcome;
+            *state.lock().unwrap() = Some(result.is_ok());
+            cvar.notify_all();
+
+            return result;
+        };
+
+        // 졌다: 이긴 쪽의 쓰기가 끝나기를 기다린 뒤, 성공했다고 그냥 믿지
+        // 않고 파일이 실제로 존재하는지 확인한다.
+        let (state, cvar) = &*outcome;
+        let mut wrote_ok = state.lock().unwrap();
+        while wrote_ok.is_none() {
+            wrote_ok = cvar.wait(wrote_ok).unwrap();
+        }
+
+        if path.exists() {
+            Ok(())
+        } else {
+            anyhow::bail!("Chunk {} was never written: concurrent writer failed", hash);
+        }
+    }
+
+    fn chunk_path(&self, hash: &str) -> PathBuf {
+        self.chunks_dir.join(hash)
+    }
+
+    /// 저장된 청크의 원본 바이트를 읽는다. 원격 백업 업로드처럼 이 저장소
+    /// 밖으로 청크를 내보내야 하는 호출자를 위한 것이다.
+    pub fn read_chunk(&self, hash: &str) -> Result<Vec<u8>> {
+        fs::read(self.chunk_path(hash)).with_context(|| format!("Reading chunk {}", hash))
+    }
+
+    /// 이 해시의 청크가 로컬 저장소에 이미 존재하는지 확인한다.
+    pub fn has_chunk(&self, hash: &str) -> bool {
+        self.chunk_path(hash).exists()
+    }
+
+    /// 원격 저장소 등 이 `ChunkStore` 밖에서 받아온 청크를 채워 넣는다.
+    /// 주장된 해시가 실제 내용의 blake3 해시와 일치하는지 확인한 뒤에만
+    /// 저장해, 손상되거나 변조된 청크가 섞여 들어오는 것을 막는다.
+    pub fn store_fetched_chunk(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let actual_hash = blake3::hash(data).to_hex().to_string();
+        if actual_hash != hash {
+            anyhow::bail!("Chunk hash mismatch: expected {}, got {}", hash, actual_hash);
+        }
+        fs::create_dir_all(&self.chunks_dir)?;
+        self.store_chunk(hash, data)
+    }
+
+    /// 저장소에 실제로 존재하는 청크 개수와 총 바이트 수를 반환한다. 청크
+    /// 내용을 읽지 않고 파일 메타데이터만 보므로 저장소가 커져도 가볍다.
+    pub fn physical_stats(&self) -> Result<(usize, u64)> {
+        if !self.chunks_dir.exists() {
+            return Ok((0, 0));
+        }
+
+        let mut count = 0;
+        let mut total_bytes = 0u64;
+        for entry in fs::read_dir(&self.chunks_dir).context("Listing chunk store")? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                count += 1;
+                total_bytes += entry.metadata()?.len();
+            }
+        }
+
+        Ok((count, total_bytes))
+    }
+
+    /// 매니페스트의 청크들을 이어붙여 `dest_dir` 아래에 원래 파일들을 복원한다.
+    pub fn restore_from_manifest(&self, manifest: &BackupManifest, dest_dir: &Path) -> Result<()> {
+        for (relative_path, chunks) in &manifest.files {
+            let dest_file = dest_dir.join(relative_path);
+            if let Some(parent) = dest_file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let mut contents = Vec::new();
+            for chunk in chunks {
+                let chunk_data = fs::read(self.chunk_path(&chunk.hash))
+                    .with_context(|| format!("Missing chunk {} for {}", chunk.hash, relative_path))?;
+                contents.extend_from_slice(&chunk_data);
+            }
+
+            fs::write(&dest_file, contents)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_chunks_are_written_once() {
+        let temp_dir = std::env::temp_dir().join("chunkstore_test_identical");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let store = ChunkStore::new(&temp_dir);
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let refs_a = store.split_and_store(&data).unwrap();
+        let refs_b = store.split_and_store(&data).unwrap();
+
+        assert_eq!(refs_a.len(), refs_b.len());
+        for (a, b) in refs_a.iter().zip(refs_b.iter()) {
+            assert_eq!(a.hash, b.hash);
+        }
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn manifest_round_trips_file_contents() {
+        let temp_dir = std::env::temp_dir().join("chunkstore_test_roundtrip");
+        let dest_dir = std::env::temp_dir().join("chunkstore_test_roundtrip_dest");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let store = ChunkStore::new(&temp_dir);
+
+        let data: Vec<u8> = (0..150_000u32).map(|i| ((i * 17) % 251) as u8).collect();
+        let chunk_refs = store.split_and_store(&data).unwrap();
+
+        let mut files = HashMap::new();
+        files.insert("sub/test.dat".to_string(), chunk_refs);
+        let manifest = BackupManifest { timestamp: 0, files };
+
+        store.restore_from_manifest(&manifest, &dest_dir).unwrap();
+        let restored = fs::read(dest_dir.join("sub/test.dat")).unwrap();
+        assert_eq!(restored, data);
+
+        fs::remove_dir_all(&temp_dir).ok();
+        fs::remove_dir_all(&dest_dir).ok();
+    }
+}
@@ -8,6 +8,7 @@
 
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, Instant, Duration, UNIX_EPOCH};
 use std::fs::{self, File, Metadata};
@@ -18,6 +19,14 @@ use anyhow::{Result, Context, bail};
 use rayon::prelude::*;
 use sha2::{Sha256, Digest};
 
+// 청크 저장소 모듈 (콘텐츠 기반 청킹 + 중복 제거)
+mod chunkstore;
+pub use chunkstore::{BackupManifest, ChunkRef, ChunkStore};
+
+// 원격 중복 제거 백업 타겟 모듈
+mod remote;
+pub use remote::{BackupReader, RemoteBackupClient};
+
 // Python 바인딩 모듈
 #[cfg(feature = "python")]
 pub mod python_bindings;
@@ -45,6 +54,44 @@ pub struct BackupResult {
     pub error_message: Option<String>,
 }
 
+/// 오래 걸리는 백업 작업의 진행 상황 스냅샷. `progress` 채널로 주기적으로
+/// (약 100ms 간격) 전송되어, 호출자(파이썬 바인딩 등)가 진행률 표시줄을
+/// 그리거나 응답 없음을 판단하는 데 쓸 수 있다.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressData {
+    pub current_stage: String,
+    pub max_stage: usize,
+    pub files_processed: usize,
+    pub files_to_process: usize,
+    pub bytes_processed: u64,
+}
+
+/// 100ms마다 진행 상황을 보내기 위한 공유 타이머. `try_lock`으로 확인하므로
+/// 바쁜 rayon 워커가 락 때문에 멈추는 일은 없다.
+struct ProgressThrottle {
+    last_sent: Mutex<Instant>,
+}
+
+impl ProgressThrottle {
+    fn new() -> Self {
+        Self { last_sent: Mutex::new(Instant::now()) }
+    }
+
+    fn maybe_send(
+        &self,
+        sender: &Option<crossbeam_channel::Sender<ProgressData>>,
+        build: impl FnOnce() -> ProgressData,
+    ) {
+        let Some(sender) = sender else { return };
+        let Ok(mut last_sent) = self.last_sent.try_lock() else { return };
+        if last_sent.elapsed() < Duration::from_millis(100) {
+            return;
+        }
+        *last_sent = Instant::now();
+        let _ = sender.try_send(build());
+    }
+}
+
 /// 모니터링 통계
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringStats {
@@ -55,6 +102,30 @@ pub struct MonitoringStats {
     pub threat_score: f64,  // 0.0 ~ 1.0
 }
 
+/// 하나의 백업 세대(generation)에 대한 통계. 이 세대에서 새로 생긴 청크만
+/// `new_chunk_bytes`/`new_chunk_count`로 집계해, 이전 백업들과 겹치는
+/// 내용을 다시 세지 않는다.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupGenerationStats {
+    pub backup_path: String,
+    pub timestamp: u64,
+    pub logical_bytes: u64,
+    pub new_chunk_bytes: u64,
+    pub new_chunk_count: usize,
+}
+
+/// 백업 저장소 전체에 대한 통계. 논리 바이트(모든 백업의 파일 크기 합)와
+/// 물리 바이트(청크 저장소의 실제 디스크 사용량)의 비율로 중복 제거가
+/// 얼마나 효과적인지 볼 수 있다.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupStats {
+    pub total_logical_bytes: u64,
+    pub total_physical_bytes: u64,
+    pub chunk_count: usize,
+    pub dedup_ratio: f64,
+    pub generations: Vec<BackupGenerationStats>,
+}
+
 /// 방어 액션 결과
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefenseActionResult {
@@ -67,9 +138,86 @@ pub struct DefenseActionResult {
 
 // ==================== 파일 모니터링 시스템 ====================
 
+/// `detect_changes`가 (size, mtime)만으로 변경 여부를 확신할 수 없을 때
+/// 먼저 해시할 앞부분 바이트 수. 이 앞부분 해시는 `FileState`에 따로
+/// 저장되고, 변경 여부는 그 뒤 항상 다시 계산하는 전체 해시로 확정한다.
+/// 앞부분 해시 비교는 전체 해시를 구하지 못했을 때만 쓰인다.
+const HASH_MB_LIMIT_BYTES: u64 = 4 * 1024 * 1024;
+
+/// 파일 해시에 사용할 알고리즘. 기본값은 `Xxh3`로, 암호학적 강도가 필요
+/// 없는 "바뀌었는지만 알면 되는" 모니터링 용도에 가장 빠르다. 무결성
+/// 증명이 필요한 맥락(백업 등)에서는 `Blake3`나 `Sha256`을 선택할 수 있다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashType {
+    Sha256,
+    Blake3,
+    Xxh3,
+}
+
+impl Default for HashType {
+    fn default() -> Self {
+        HashType::Xxh3
+    }
+}
+
+impl HashType {
+    /// `path`의 처음 `limit` 바이트(파일이 더 작으면 전체)를 해시한다.
+    fn hash_prefix(self, path: &Path, limit: u64) -> Result<String> {
+        let file = File::open(path)?;
+        self.hash_reader(file.take(limit))
+    }
+
+    /// `path` 전체를 해시한다.
+    fn hash_full(self, path: &Path) -> Result<String> {
+        let file = File::open(path)?;
+        self.hash_reader(file)
+    }
+
+    fn hash_reader<R: Read>(self, mut reader: R) -> Result<String> {
+        let mut buffer = [0u8; 8192];
+        match self {
+            HashType::Sha256 => {
+                let mut hasher = Sha256::new();
+                loop {
+                    let n = reader.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..n]);
+                }
+                Ok(format!("{:x}", hasher.finalize()))
+            }
+            HashType::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                loop {
+                    let n = reader.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..n]);
+                }
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+            HashType::Xxh3 => {
+                let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+                loop {
+                    let n = reader.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..n]);
+                }
+                Ok(format!("{:016x}", hasher.digest()))
+            }
+        }
+    }
+}
+
 /// 파일 시스템 모니터
 pub struct FileSystemMonitor {
     watch_paths: Vec<PathBuf>,
+    hash_type: HashType,
+    cache_path: Option<PathBuf>,
     file_states: Arc<Mutex<HashMap<PathBuf, FileState>>>,
     change_events: Arc<Mutex<Vec<FileChangeEvent>>>,
 }
@@ -78,105 +226,282 @@ pub struct FileSystemMonitor {
 struct FileState {
     last_modified: SystemTime,
     size: u64,
-    hash: String,
+    prefix_hash: String,
+    full_hash: Option<String>,
+}
+
+/// 디스크에 영속화하는 `FileState`의 직렬화 가능한 형태. `SystemTime`은
+/// serde를 직접 구현하지 않으므로 초/나노초로 풀어서 저장한다.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    size: u64,
+    prefix_hash: String,
+    full_hash: Option<String>,
+}
+
+/// `cache_path`에 저장되는 전체 인덱스. 경로별로 마지막으로 관찰한
+/// `IndexEntry`를 담는다 (dirstate와 같은 구조).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct PersistedIndex {
+    entries: HashMap<String, IndexEntry>,
+}
+
+impl FileState {
+    fn to_index_entry(&self) -> IndexEntry {
+        let since_epoch = self.last_modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+        IndexEntry {
+            mtime_secs: since_epoch.as_secs(),
+            mtime_nanos: since_epoch.subsec_nanos(),
+            size: self.size,
+            prefix_hash: self.prefix_hash.clone(),
+            full_hash: self.full_hash.clone(),
+        }
+    }
+
+    fn from_index_entry(entry: &IndexEntry) -> Self {
+        Self {
+            last_modified: UNIX_EPOCH + Duration::new(entry.mtime_secs, entry.mtime_nanos),
+            size: entry.size,
+            prefix_hash: entry.prefix_hash.clone(),
+            full_hash: entry.full_hash.clone(),
+        }
+    }
 }
 
 impl FileSystemMonitor {
     pub fn new(watch_paths: Vec<PathBuf>) -> Self {
+        Self::with_hash_type(watch_paths, HashType::default())
+    }
+
+    pub fn with_hash_type(watch_paths: Vec<PathBuf>, hash_type: HashType) -> Self {
+        Self {
+            watch_paths,
+            hash_type,
+            cache_path: None,
+            file_states: Arc::new(Mutex::new(HashMap::new())),
+            change_events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// `cache_path`에 상태 인덱스를 영속화해, 재시작 사이에 모니터링이
+    /// 끊긴 동안의 변경도 `initial_scan`에서 감지할 수 있게 한다.
+    pub fn with_cache(watch_paths: Vec<PathBuf>, hash_type: HashType, cache_path: PathBuf) -> Self {
         Self {
             watch_paths,
+            hash_type,
+            cache_path: Some(cache_path),
             file_states: Arc::new(Mutex::new(HashMap::new())),
             change_events: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
-    /// 초기 파일 상태 스캔
+    /// `cache_path`에서 이전 세션의 인덱스를 읽어온다. 캐시가 설정되지
+    /// 않았거나 아직 파일이 없으면 빈 인덱스를 반환한다.
+    pub fn load_index(&self) -> Result<HashMap<PathBuf, FileState>> {
+        let Some(cache_path) = &self.cache_path else {
+            return Ok(HashMap::new());
+        };
+        if !cache_path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let bytes = fs::read(cache_path).context("Reading file-state index")?;
+        let persisted: PersistedIndex = bincode::deserialize(&bytes).context("Parsing file-state index")?;
+
+        Ok(persisted.entries
+            .into_iter()
+            .map(|(path, entry)| (PathBuf::from(path), FileState::from_index_entry(&entry)))
+            .collect())
+    }
+
+    /// 현재 `file_states`를 `cache_path`에 원자적으로(임시 파일 쓰고 rename)
+    /// 저장한다. 캐시가 설정되지 않았으면 아무것도 하지 않는다.
+    pub fn save_index(&self) -> Result<()> {
+        let Some(cache_path) = &self.cache_path else {
+            return Ok(());
+        };
+
+        let states = self.file_states.lock().unwrap();
+        let entries = states
+            .iter()
+            .map(|(path, state)| (path.to_string_lossy().to_string(), state.to_index_entry()))
+            .collect();
+        drop(states);
+
+        let persisted = PersistedIndex { entries };
+        let bytes = bincode::serialize(&persisted).context("Serializing file-state index")?;
+
+        if let Some(parent) = cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = cache_path.with_extension("tmp");
+        fs::write(&tmp_path, bytes).context("Writing temporary file-state index")?;
+        fs::rename(&tmp_path, cache_path).context("Publishing file-state index")?;
+
+        Ok(())
+    }
+
+    fn record_drift(&self, path: &Path, event_type: &str, file_size: u64) {
+        self.change_events.lock().unwrap().push(FileChangeEvent {
+            path: path.to_string_lossy().to_string(),
+            event_type: event_type.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            file_size,
+            file_hash: None,
+        });
+    }
+
+    /// 초기 파일 상태 스캔. 캐시가 있으면 먼저 읽어와서, 모니터링이 꺼져
+    /// 있던 동안 생긴 생성/수정/삭제를 `change_events`에 기록한다.
     pub fn initial_scan(&self) -> Result<usize> {
         let mut count = 0;
+        let previous = self.load_index().unwrap_or_default();
+        let mut seen = HashSet::new();
         let mut states = self.file_states.lock().unwrap();
-        
+
         for watch_path in &self.watch_paths {
             if !watch_path.exists() {
                 continue;
             }
-            
+
             let entries = walkdir::WalkDir::new(watch_path)
                 .follow_links(false)
                 .into_iter()
                 .filter_map(|e| e.ok())
                 .filter(|e| e.file_type().is_file());
-            
+
             for entry in entries {
                 let path = entry.path().to_path_buf();
                 if let Ok(metadata) = fs::metadata(&path) {
                     if let Ok(modified) = metadata.modified() {
-                        let hash = Self::calculate_file_hash(&path).unwrap_or_default();
+                        seen.insert(path.clone());
+
+                        match previous.get(&path) {
+                            Some(prev) if prev.size == metadata.len() && prev.last_modified == modified => {}
+                            Some(_) => self.record_drift(&path, "modified", metadata.len()),
+                            None => self.record_drift(&path, "created", metadata.len()),
+                        }
+
+                        let prefix_hash = self.hash_type
+                            .hash_prefix(&path, HASH_MB_LIMIT_BYTES)
+                            .unwrap_or_default();
                         states.insert(path, FileState {
                             last_modified: modified,
                             size: metadata.len(),
-                            hash,
+                            prefix_hash,
+                            full_hash: None,
                         });
                         count += 1;
                     }
                 }
             }
         }
-        
+
+        for path in previous.keys() {
+            if !seen.contains(path) {
+                self.record_drift(path, "deleted", 0);
+            }
+        }
+
+        drop(states);
+        self.save_index()?;
+
         Ok(count)
     }
 
-    /// 변경 사항 감지
+    /// 변경 사항 감지. `full_verify`가 true면 (size, mtime)이 그대로여도
+    /// 항상 전체 파일을 다시 해시해 확인한다 (시계가 틀어졌거나 mtime을
+    /// 보존한 채 내용을 바꾸는 랜섬웨어를 의심할 때 유용).
     pub fn detect_changes(&self) -> Result<Vec<FileChangeEvent>> {
+        self.detect_changes_with(false)
+    }
+
+    pub fn detect_changes_with(&self, full_verify: bool) -> Result<Vec<FileChangeEvent>> {
         let mut events = Vec::new();
         let mut states = self.file_states.lock().unwrap();
-        
+
         for watch_path in &self.watch_paths {
             if !watch_path.exists() {
                 continue;
             }
-            
+
             let entries = walkdir::WalkDir::new(watch_path)
                 .follow_links(false)
                 .into_iter()
                 .filter_map(|e| e.ok())
                 .filter(|e| e.file_type().is_file());
-            
+
             for entry in entries {
                 let path = entry.path().to_path_buf();
-                if let Ok(metadata) = fs::metadata(&path) {
-                    if let Ok(modified) = metadata.modified() {
-                        let current_hash = Self::calculate_file_hash(&path).unwrap_or_default();
-                        
-                        let event_type = if let Some(state) = states.get(&path) {
-                            if state.hash != current_hash {
-                                "modified"
-                            } else {
-                                continue;
-                            }
-                        } else {
-                            "created"
-                        };
-                        
-                        let event = FileChangeEvent {
-                            path: path.to_string_lossy().to_string(),
-                            event_type: event_type.to_string(),
-                            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                            file_size: metadata.len(),
-                            file_hash: Some(current_hash.clone()),
-                        };
-                        
-                        events.push(event);
-                        
-                        states.insert(path, FileState {
-                            last_modified: modified,
-                            size: metadata.len(),
-                            hash: current_hash,
-                        });
+                let Ok(metadata) = fs::metadata(&path) else { continue };
+                let Ok(modified) = metadata.modified() else { continue };
+
+                let existing = states.get(&path).cloned();
+
+                // 1단계: (size, mtime)이 그대로고 강제 확인을 요청하지 않았다면
+                // 변경이 없다고 보고 전체 파일을 다시 읽지 않는다.
+                if let Some(state) = &existing {
+                    if !full_verify && state.size == metadata.len() && state.last_modified == modified {
+                        continue;
                     }
                 }
+
+                // 2단계: 앞부분 해시를 구한다. 상태에 저장해 두고, 전체 해시를
+                // 구하지 못했을 때의 변경 판단에 쓴다.
+                let Ok(prefix_hash) = self.hash_type.hash_prefix(&path, HASH_MB_LIMIT_BYTES) else { continue };
+                let prefix_changed = existing.as_ref().map(|s| s.prefix_hash != prefix_hash).unwrap_or(true);
+
+                // 3단계: 전체 해시로 확정한다. 이 지점에 도달했다는 것 자체가
+                // (1단계의 조기 continue가 없었으므로) size/mtime이 이미
+                // 바뀌었거나 강제 확인이 요청되었다는 뜻이므로, 앞부분
+                // (prefix_hash)이 우연히 같더라도 캐시된 full_hash를 재사용하지
+                // 않고 항상 다시 계산한다 — 그렇지 않으면 첫 4MB 이후에서만
+                // 바뀐 내용(대용량 파일 뒷부분을 암호화하는 랜섬웨어 등)을
+                // 영영 놓치게 된다.
+                let full_hash = self.hash_type.hash_full(&path).ok();
+
+                let changed = match (&existing, &full_hash) {
+                    (Some(state), Some(hash)) => state.full_hash.as_deref() != Some(hash.as_str()),
+                    (Some(_), None) => prefix_changed,
+                    (None, _) => true,
+                };
+
+                if !changed {
+                    states.insert(path, FileState {
+                        last_modified: modified,
+                        size: metadata.len(),
+                        prefix_hash,
+                        full_hash,
+                    });
+                    continue;
+                }
+
+                let event_type = if existing.is_some() { "modified" } else { "created" };
+
+                let event = FileChangeEvent {
+                    path: path.to_string_lossy().to_string(),
+                    event_type: event_type.to_string(),
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                    file_size: metadata.len(),
+                    file_hash: full_hash.clone().or_else(|| Some(prefix_hash.clone())),
+                };
+
+                events.push(event);
+
+                states.insert(path, FileState {
+                    last_modified: modified,
+                    size: metadata.len(),
+                    prefix_hash,
+                    full_hash,
+                });
             }
         }
-        
+
+        drop(states);
+        self.save_index()?;
+
         Ok(events)
     }
 
@@ -215,22 +540,6 @@ impl FileSystemMonitor {
         })
     }
 
-    /// 파일 해시 계산
-    fn calculate_file_hash(path: &Path) -> Result<String> {
-        let mut file = File::open(path)?;
-        let mut hasher = Sha256::new();
-        let mut buffer = [0u8; 8192];
-        
-        loop {
-            let n = file.read(&mut buffer)?;
-            if n == 0 {
-                break;
-            }
-            hasher.update(&buffer[..n]);
-        }
-        
-        Ok(format!("{:x}", hasher.finalize()))
-    }
 }
 
 // ==================== 초고속 증분 백업 시스템 ====================
@@ -239,27 +548,43 @@ impl FileSystemMonitor {
 pub struct IncrementalBackupEngine {
     source_dir: PathBuf,
     backup_dir: PathBuf,
+    chunk_store: ChunkStore,
     last_backup_time: Arc<Mutex<Option<SystemTime>>>,
 }
 
 impl IncrementalBackupEngine {
     pub fn new(source_dir: PathBuf, backup_dir: PathBuf) -> Self {
+        let chunk_store = ChunkStore::new(&backup_dir);
         Self {
             source_dir,
             backup_dir,
+            chunk_store,
             last_backup_time: Arc::new(Mutex::new(None)),
         }
     }
 
-    /// 증분 백업 실행 (병렬 처리)
+    /// 증분 백업 실행 (병렬 처리). 파일을 통째로 복사하는 대신 각 파일을
+    /// FastCDC 청크로 나눠 청크 저장소에 쓰고(이미 있는 청크는 건너뜀),
+    /// 백업 폴더에는 상대 경로 -> 청크 해시 목록을 담은 매니페스트만 남긴다.
     pub fn execute_backup(&self) -> Result<BackupResult> {
+        self.execute_backup_with_progress(None, None)
+    }
+
+    /// `execute_backup`과 동일하지만, `progress`가 주어지면 약 100ms마다
+    /// 진행 상황을 보내고 `stop_flag`가 set되면 아직 처리하지 않은 파일은
+    /// 건너뛰고 그때까지의 결과로 조기 종료한다.
+    pub fn execute_backup_with_progress(
+        &self,
+        progress: Option<crossbeam_channel::Sender<ProgressData>>,
+        stop_flag: Option<Arc<AtomicBool>>,
+    ) -> Result<BackupResult> {
         let start_time = Instant::now();
-        
+
         // 백업 디렉토리 생성
         if !self.backup_dir.exists() {
             fs::create_dir_all(&self.backup_dir)?;
         }
-        
+
         // 타임스탬프 기반 백업 폴더
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -267,7 +592,7 @@ impl IncrementalBackupEngine {
             .as_secs();
         let backup_path = self.backup_dir.join(format!("backup_{}", timestamp));
         fs::create_dir_all(&backup_path)?;
-        
+
         // 변경된 파일 식별
         let last_backup = *self.last_backup_time.lock().unwrap();
         let files_to_backup: Vec<PathBuf> = walkdir::WalkDir::new(&self.source_dir)
@@ -291,82 +616,258 @@ impl IncrementalBackupEngine {
                 None
             })
             .collect();
-        
-        // 병렬 백업 실행 (rayon 사용)
-        let total_bytes: u64 = files_to_backup
+
+        let files_to_process = files_to_backup.len();
+        let files_processed = AtomicUsize::new(0);
+        let bytes_processed = AtomicUsize::new(0);
+        let throttle = ProgressThrottle::new();
+        let stopped_early = AtomicBool::new(false);
+
+        // 병렬로 청크 저장 (rayon 사용); 각 파일의 매니페스트 항목을 수집
+        let entries: Vec<(String, Vec<ChunkRef>, u64)> = files_to_backup
             .par_iter()
-            .map(|source_file| {
-                let relative_path = source_file.strip_prefix(&self.source_dir).unwrap();
-                let dest_file = backup_path.join(relative_path);
-                
-                // 디렉토리 생성
-                if let Some(parent) = dest_file.parent() {
-                    let _ = fs::create_dir_all(parent);
-                }
-                
-                // 파일 복사
-                if let Ok(_) = fs::copy(source_file, &dest_file) {
-                    fs::metadata(&dest_file).map(|m| m.len()).unwrap_or(0)
-                } else {
-                    0
+            .filter_map(|source_file| {
+                if stop_flag.as_ref().map(|f| f.load(Ordering::Relaxed)).unwrap_or(false) {
+                    stopped_early.store(true, Ordering::Relaxed);
+                    return None;
                 }
+
+                let relative_path = source_file.strip_prefix(&self.source_dir).ok()?;
+                let data = fs::read(source_file).ok()?;
+                let chunk_refs = self.chunk_store.split_and_store(&data).ok()?;
+                let total_len: u64 = chunk_refs.iter().map(|c| c.len).sum();
+
+                let processed = files_processed.fetch_add(1, Ordering::Relaxed) + 1;
+                let bytes = bytes_processed.fetch_add(total_len as usize, Ordering::Relaxed) + total_len as usize;
+                throttle.maybe_send(&progress, || ProgressData {
+                    current_stage: "chunking".to_string(),
+                    max_stage: 1,
+                    files_processed: processed,
+                    files_to_process,
+                    bytes_processed: bytes as u64,
+                });
+
+                Some((relative_path.to_string_lossy().to_string(), chunk_refs, total_len))
             })
-            .sum();
-        
+            .collect();
+
+        let total_bytes: u64 = entries.iter().map(|(_, _, len)| *len).sum();
+        let files: HashMap<String, Vec<ChunkRef>> = entries
+            .into_iter()
+            .map(|(path, chunk_refs, _)| (path, chunk_refs))
+            .collect();
+
+        let manifest = BackupManifest { timestamp, files };
+        let manifest_path = backup_path.join("manifest.json");
+        fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+
+        if let Some(sender) = &progress {
+            let _ = sender.try_send(ProgressData {
+                current_stage: "done".to_string(),
+                max_stage: 1,
+                files_processed: manifest.files.len(),
+                files_to_process,
+                bytes_processed: total_bytes,
+            });
+        }
+
         let duration = start_time.elapsed();
-        
+
         // 마지막 백업 시간 업데이트
         *self.last_backup_time.lock().unwrap() = Some(SystemTime::now());
-        
+
         Ok(BackupResult {
             success: true,
-            files_backed_up: files_to_backup.len(),
+            files_backed_up: manifest.files.len(),
             total_bytes,
             duration_ms: duration.as_millis() as u64,
             backup_path: backup_path.to_string_lossy().to_string(),
-            error_message: None,
+            error_message: if stopped_early.load(Ordering::Relaxed) {
+                Some("Backup stopped early; only partially backed up".to_string())
+            } else {
+                None
+            },
+        })
+    }
+
+    /// `backup_path`(execute_backup이 만든 백업 폴더)의 매니페스트를 읽어
+    /// `dest_dir` 아래에 파일들을 복원한다.
+    pub fn restore_from_manifest(&self, backup_path: &Path, dest_dir: &Path) -> Result<usize> {
+        let manifest = self.read_manifest(backup_path)?;
+        let file_count = manifest.files.len();
+        self.chunk_store.restore_from_manifest(&manifest, dest_dir)?;
+        Ok(file_count)
+    }
+
+    fn read_manifest(&self, backup_path: &Path) -> Result<BackupManifest> {
+        let manifest_content = fs::read_to_string(backup_path.join("manifest.json"))
+            .context("Failed to read backup manifest")?;
+        Ok(serde_json::from_str(&manifest_content)?)
+    }
+
+    /// `backup_path`의 매니페스트가 참조하는 청크 중 원격 서버에 없는 것만
+    /// 업로드하고, 매니페스트도 함께 올려 오프사이트 복사본을 만든다.
+    pub fn push_offsite(&self, backup_path: &Path, remote: &mut RemoteBackupClient) -> Result<()> {
+        let manifest = self.read_manifest(backup_path)?;
+        let chunk_hashes: Vec<String> = manifest.files
+            .values()
+            .flat_map(|chunks| chunks.iter().map(|c| c.hash.clone()))
+            .collect();
+
+        remote.upload_missing_chunks(&self.chunk_store, &chunk_hashes)?;
+        remote.upload_manifest(&manifest)?;
+        Ok(())
+    }
+
+    /// 백업 저장소 전체의 통계를 계산한다. 각 백업 폴더의 매니페스트는
+    /// 이미 청크 길이를 담고 있으므로, 논리 바이트 계산에는 청크 내용을
+    /// 다시 읽을 필요가 없다. 물리 바이트만 청크 저장소 디렉토리를 훑어
+    /// (메타데이터만) 구한다.
+    pub fn stats(&self) -> Result<BackupStats> {
+        if !self.backup_dir.exists() {
+            return Ok(BackupStats {
+                total_logical_bytes: 0,
+                total_physical_bytes: 0,
+                chunk_count: 0,
+                dedup_ratio: 0.0,
+                generations: Vec::new(),
+            });
+        }
+
+        let mut manifests: Vec<(String, BackupManifest)> = fs::read_dir(&self.backup_dir)
+            .context("Listing backup directory")?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| {
+                let manifest = self.read_manifest(&e.path()).ok()?;
+                Some((e.path().to_string_lossy().to_string(), manifest))
+            })
+            .collect();
+        manifests.sort_by_key(|(_, manifest)| manifest.timestamp);
+
+        let mut seen_chunks: HashMap<String, u64> = HashMap::new();
+        let mut total_logical_bytes = 0u64;
+        let mut generations = Vec::with_capacity(manifests.len());
+
+        for (backup_path, manifest) in manifests {
+            let mut logical_bytes = 0u64;
+            let mut new_chunk_bytes = 0u64;
+            let mut new_chunk_count = 0usize;
+
+            for chunks in manifest.files.values() {
+                for chunk in chunks {
+                    logical_bytes += chunk.len;
+                    if seen_chunks.insert(chunk.hash.clone(), chunk.len).is_none() {
+                        new_chunk_bytes += chunk.len;
+                        new_chunk_count += 1;
+                    }
+                }
+            }
+
+            total_logical_bytes += logical_bytes;
+            generations.push(BackupGenerationStats {
+                backup_path,
+                timestamp: manifest.timestamp,
+                logical_bytes,
+                new_chunk_bytes,
+                new_chunk_count,
+            });
+        }
+
+        let (chunk_count, total_physical_bytes) = self.chunk_store.physical_stats()?;
+        let dedup_ratio = if total_physical_bytes > 0 {
+            total_logical_bytes as f64 / total_physical_bytes as f64
+        } else {
+            0.0
+        };
+
+        Ok(BackupStats {
+            total_logical_bytes,
+            total_physical_bytes,
+            chunk_count,
+            dedup_ratio,
+            generations,
         })
     }
 
     /// 특정 파일들만 백업 (AI 예측 기반)
     pub fn backup_specific_files(&self, file_paths: &[PathBuf]) -> Result<BackupResult> {
+        self.backup_specific_files_with_progress(file_paths, None, None)
+    }
+
+    /// `backup_specific_files`와 동일하지만 진행 상황 보고와 취소를 지원한다.
+    pub fn backup_specific_files_with_progress(
+        &self,
+        file_paths: &[PathBuf],
+        progress: Option<crossbeam_channel::Sender<ProgressData>>,
+        stop_flag: Option<Arc<AtomicBool>>,
+    ) -> Result<BackupResult> {
         let start_time = Instant::now();
-        
+
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
         let backup_path = self.backup_dir.join(format!("emergency_backup_{}", timestamp));
         fs::create_dir_all(&backup_path)?;
-        
+
+        let files_processed = AtomicUsize::new(0);
+        let bytes_processed = AtomicUsize::new(0);
+        let throttle = ProgressThrottle::new();
+        let stopped_early = AtomicBool::new(false);
+
         // 병렬 백업
         let total_bytes: u64 = file_paths
             .par_iter()
             .map(|source_file| {
-                if let Ok(relative_path) = source_file.strip_prefix(&self.source_dir) {
+                if stop_flag.as_ref().map(|f| f.load(Ordering::Relaxed)).unwrap_or(false) {
+                    stopped_early.store(true, Ordering::Relaxed);
+                    return 0;
+                }
+
+                let copied = if let Ok(relative_path) = source_file.strip_prefix(&self.source_dir) {
                     let dest_file = backup_path.join(relative_path);
-                    
+
                     if let Some(parent) = dest_file.parent() {
                         let _ = fs::create_dir_all(parent);
                     }
-                    
-                    if let Ok(_) = fs::copy(source_file, &dest_file) {
-                        return fs::metadata(&dest_file).map(|m| m.len()).unwrap_or(0);
+
+                    if fs::copy(source_file, &dest_file).is_ok() {
+                        fs::metadata(&dest_file).map(|m| m.len()).unwrap_or(0)
+                    } else {
+                        0
                     }
-                }
-                0
+                } else {
+                    0
+                };
+
+                let processed = files_processed.fetch_add(1, Ordering::Relaxed) + 1;
+                let bytes = bytes_processed.fetch_add(copied as usize, Ordering::Relaxed) + copied as usize;
+                throttle.maybe_send(&progress, || ProgressData {
+                    current_stage: "emergency_copy".to_string(),
+                    max_stage: 1,
+                    files_processed: processed,
+                    files_to_process: file_paths.len(),
+                    bytes_processed: bytes as u64,
+                });
+
+                copied
             })
             .sum();
-        
+
         let duration = start_time.elapsed();
-        
+
         Ok(BackupResult {
             success: true,
             files_backed_up: file_paths.len(),
             total_bytes,
             duration_ms: duration.as_millis() as u64,
             backup_path: backup_path.to_string_lossy().to_string(),
-            error_message: None,
+            error_message: if stopped_early.load(Ordering::Relaxed) {
+                Some("Backup stopped early; only partially backed up".to_string())
+            } else {
+                None
+            },
         })
     }
 }
@@ -458,24 +959,48 @@ impl IsolatedWriteManager {
 
     /// 클린 로그를 주 서버로 동기화
     pub fn sync_clean_logs(&self) -> Result<usize> {
+        self.sync_clean_logs_with_progress(None, None)
+    }
+
+    /// `sync_clean_logs`와 동일하지만 진행 상황 보고와 취소를 지원한다.
+    /// `stop_flag`가 set되면 아직 동기화하지 않은 항목은 건너뛰고 지금까지
+    /// 동기화한 개수를 반환한다.
+    pub fn sync_clean_logs_with_progress(
+        &self,
+        progress: Option<crossbeam_channel::Sender<ProgressData>>,
+        stop_flag: Option<Arc<AtomicBool>>,
+    ) -> Result<usize> {
         let write_log = self.write_log.lock().unwrap();
+        let throttle = ProgressThrottle::new();
         let mut synced_count = 0;
-        
+
         for operation in write_log.iter() {
+            if stop_flag.as_ref().map(|f| f.load(Ordering::Relaxed)).unwrap_or(false) {
+                break;
+            }
+
             let source = PathBuf::from(&operation.redirected_path);
             let dest = PathBuf::from(&operation.original_path);
-            
+
             if source.exists() {
                 if let Some(parent) = dest.parent() {
                     let _ = fs::create_dir_all(parent);
                 }
-                
+
                 if fs::copy(&source, &dest).is_ok() {
                     synced_count += 1;
                 }
             }
+
+            throttle.maybe_send(&progress, || ProgressData {
+                current_stage: "sync_clean_logs".to_string(),
+                max_stage: 1,
+                files_processed: synced_count,
+                files_to_process: write_log.len(),
+                bytes_processed: 0,
+            });
         }
-        
+
         Ok(synced_count)
     }
 
@@ -492,6 +1017,7 @@ pub struct DefenseEngine {
     monitor: FileSystemMonitor,
     backup_engine: IncrementalBackupEngine,
     write_manager: IsolatedWriteManager,
+    remote: Option<Mutex<RemoteBackupClient>>,
 }
 
 impl DefenseEngine {
@@ -505,9 +1031,25 @@ impl DefenseEngine {
             monitor: FileSystemMonitor::new(watch_paths),
             backup_engine: IncrementalBackupEngine::new(source_dir.clone(), backup_dir),
             write_manager: IsolatedWriteManager::new(source_dir, clean_log_dir),
+            remote: None,
         }
     }
 
+    /// `new`와 동일하지만, 긴급 백업(threat_score > 0.7) 발생 시 로컬 백업에
+    /// 더해 `remote_url`의 원격 서버로도 오프사이트 복사본을 올린다.
+    pub fn with_remote(
+        watch_paths: Vec<PathBuf>,
+        source_dir: PathBuf,
+        backup_dir: PathBuf,
+        clean_log_dir: PathBuf,
+        remote_url: impl Into<String>,
+        started_at_secs: u64,
+    ) -> Self {
+        let mut engine = Self::new(watch_paths, source_dir, backup_dir, clean_log_dir);
+        engine.remote = Some(Mutex::new(RemoteBackupClient::new(remote_url, started_at_secs)));
+        engine
+    }
+
     /// 초기화
     pub fn initialize(&self) -> Result<usize> {
         self.monitor.initial_scan()
@@ -515,30 +1057,56 @@ impl DefenseEngine {
 
     /// AI 예측에 기반한 방어 액션 실행
     pub fn execute_defense_action(&self, threat_score: f64) -> Result<DefenseActionResult> {
+        self.execute_defense_action_with_progress(threat_score, None, None)
+    }
+
+    /// `execute_defense_action`과 동일하지만, 백업 단계의 진행 상황을
+    /// `progress`로 보고하고 `stop_flag`로 취소할 수 있다. 위협이 감지된
+    /// 상황에서 파이썬 바인딩이 긴급 백업 진행률을 표시하거나 사용자가
+    /// 중단을 요청할 수 있게 한다.
+    pub fn execute_defense_action_with_progress(
+        &self,
+        threat_score: f64,
+        progress: Option<crossbeam_channel::Sender<ProgressData>>,
+        stop_flag: Option<Arc<AtomicBool>>,
+    ) -> Result<DefenseActionResult> {
         let start_time = Instant::now();
-        
+
         if threat_score > 0.7 {
             // 긴급 백업 + 격리 모드 활성화
-            let backup_result = self.backup_engine.execute_backup()?;
+            let backup_result = self.backup_engine.execute_backup_with_progress(progress, stop_flag)?;
             self.write_manager.activate_isolation()?;
-            
+
+            // 원격 백업 타겟이 설정되어 있으면 오프사이트 복사본도 올린다.
+            // 실패해도 로컬 백업과 격리는 이미 끝났으니 액션 자체를 실패
+            // 처리하지는 않고, 경고만 남긴다.
+            let local_ok = backup_result.error_message.is_none();
+            let mut error_message = backup_result.error_message;
+            if let Some(remote) = &self.remote {
+                let mut remote = remote.lock().unwrap();
+                if let Err(err) = self.backup_engine.push_offsite(Path::new(&backup_result.backup_path), &mut remote) {
+                    eprintln!("Offsite backup push failed: {}", err);
+                    error_message.get_or_insert_with(|| format!("Offsite push failed: {}", err));
+                }
+            }
+
             Ok(DefenseActionResult {
                 action_type: "emergency_backup_and_isolation".to_string(),
-                success: true,
+                success: local_ok,
                 duration_ms: start_time.elapsed().as_millis() as u64,
                 protected_files: backup_result.files_backed_up,
-                error_message: None,
+                error_message,
             })
         } else if threat_score > 0.4 {
             // 증분 백업만 실행
-            let backup_result = self.backup_engine.execute_backup()?;
-            
+            let backup_result = self.backup_engine.execute_backup_with_progress(progress, stop_flag)?;
+
             Ok(DefenseActionResult {
                 action_type: "incremental_backup".to_string(),
-                success: true,
+                success: backup_result.error_message.is_none(),
                 duration_ms: start_time.elapsed().as_millis() as u64,
                 protected_files: backup_result.files_backed_up,
-                error_message: None,
+                error_message: backup_result.error_message,
             })
         } else {
             // 모니터링만 지속
@@ -557,6 +1125,11 @@ impl DefenseEngine {
         self.monitor.get_monitoring_stats(10)
     }
 
+    /// 백업 저장소 통계 조회 (논리/물리 바이트, 중복 제거율, 세대별 증가량)
+    pub fn get_backup_stats(&self) -> Result<BackupStats> {
+        self.backup_engine.stats()
+    }
+
     /// 공격 종료 후 복구
     pub fn restore_normal_operations(&self) -> Result<usize> {
         self.write_manager.deactivate_isolation()?;
@@ -7,16 +7,20 @@ use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList};
 use std::path::PathBuf;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use crate::{
     DefenseEngine, BackupResult, MonitoringStats, DefenseActionResult,
-    FileChangeEvent, WriteOperation, benchmark_defense_speed,
+    FileChangeEvent, WriteOperation, ProgressData, benchmark_defense_speed,
 };
 
 /// Python 노출용 DefenseEngine 래퍼
 #[pyclass]
 pub struct PyDefenseEngine {
     engine: DefenseEngine,
+    stop_flag: Arc<AtomicBool>,
+    last_progress: Arc<Mutex<Option<ProgressData>>>,
 }
 
 #[pymethods]
@@ -35,8 +39,12 @@ impl PyDefenseEngine {
             PathBuf::from(backup_dir),
             PathBuf::from(clean_log_dir),
         );
-        
-        Ok(Self { engine })
+
+        Ok(Self {
+            engine,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            last_progress: Arc::new(Mutex::new(None)),
+        })
     }
 
     /// 초기화
@@ -45,11 +53,32 @@ impl PyDefenseEngine {
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
     }
 
-    /// 방어 액션 실행 (AI 예측 기반)
+    /// 방어 액션 실행 (AI 예측 기반). 백업 진행 상황은 `get_progress`로
+    /// 조회할 수 있고, `request_stop`을 호출하면 다음 진행 상황 체크
+    /// 시점에 남은 파일을 건너뛰고 조기 종료한다.
     pub fn execute_defense(&self, threat_score: f64) -> PyResult<PyObject> {
-        let result = self.engine.execute_defense_action(threat_score)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
-        
+        self.stop_flag.store(false, Ordering::Relaxed);
+        *self.last_progress.lock().unwrap() = None;
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let last_progress = self.last_progress.clone();
+        let drain_thread = std::thread::spawn(move || {
+            while let Ok(progress) = rx.recv() {
+                *last_progress.lock().unwrap() = Some(progress);
+            }
+        });
+
+        // 백업 동안 GIL을 놓아 두어야 다른 파이썬 스레드에서
+        // `get_progress`/`request_stop`을 호출할 수 있다.
+        let stop_flag = self.stop_flag.clone();
+        let engine = &self.engine;
+        let result = Python::with_gil(|py| py.allow_threads(move || engine.execute_defense_action_with_progress(
+            threat_score,
+            Some(tx),
+            Some(stop_flag),
+        ))).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let _ = drain_thread.join();
+
         Python::with_gil(|py| {
             let dict = PyDict::new(py);
             dict.set_item("action_type", result.action_type)?;
@@ -61,6 +90,27 @@ impl PyDefenseEngine {
         })
     }
 
+    /// 진행 중인 방어 액션을 중단하도록 요청한다.
+    pub fn request_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// 마지막으로 보고된 백업 진행 상황(없으면 None)을 반환한다.
+    pub fn get_progress(&self) -> PyResult<Option<PyObject>> {
+        let progress = self.last_progress.lock().unwrap().clone();
+        let Some(progress) = progress else { return Ok(None) };
+
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("current_stage", progress.current_stage)?;
+            dict.set_item("max_stage", progress.max_stage)?;
+            dict.set_item("files_processed", progress.files_processed)?;
+            dict.set_item("files_to_process", progress.files_to_process)?;
+            dict.set_item("bytes_processed", progress.bytes_processed)?;
+            Ok(Some(dict.into()))
+        })
+    }
+
     /// 모니터링 통계 조회
     pub fn get_monitoring_stats(&self) -> PyResult<PyObject> {
         let stats = self.engine.get_stats()
@@ -77,6 +127,22 @@ impl PyDefenseEngine {
         })
     }
 
+    /// 백업 저장소 통계 조회 (중복 제거율 등)
+    pub fn get_backup_stats(&self) -> PyResult<PyObject> {
+        let stats = self.engine.get_backup_stats()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("total_logical_bytes", stats.total_logical_bytes)?;
+            dict.set_item("total_physical_bytes", stats.total_physical_bytes)?;
+            dict.set_item("chunk_count", stats.chunk_count)?;
+            dict.set_item("dedup_ratio", stats.dedup_ratio)?;
+            dict.set_item("generation_count", stats.generations.len())?;
+            Ok(dict.into())
+        })
+    }
+
     /// 정상 운영 복구
     pub fn restore_normal_operations(&self) -> PyResult<usize> {
         self.engine.restore_normal_operations()
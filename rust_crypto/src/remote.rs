@@ -0,0 +1,255 @@
+/*
+ * 원격 중복 제거 백업 타겟
+ *
+ * 로컬 ChunkStore에 쌓인 청크를 append-only 원격 서버로 내보낸다.
+ * 업로드 전에 서버가 이미 갖고 있는 청크 해시 목록을 물어봐서("known-chunk
+ * negotiation"), 없는 청크만 전송함으로써 대역폭을 아낀다.
+ */
+
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::chunkstore::{BackupManifest, ChunkStore};
+
+/// 재시도할 최대 횟수. 이 횟수를 넘겨도 실패하면 호출자에게 에러를 올린다.
+const MAX_RETRIES: u32 = 3;
+/// 재시도 사이 기본 대기 시간. 시도할 때마다 2배씩 늘어난다(지수 백오프).
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Serialize)]
+struct KnownChunksRequest<'a> {
+    session_id: &'a str,
+    chunk_hashes: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct KnownChunksResponse {
+    known_hashes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct UploadChunkRequest<'a> {
+    session_id: &'a str,
+    hash: &'a str,
+    data: &'a [u8],
+}
+
+#[derive(Debug, Serialize)]
+struct FinalizeSessionRequest<'a> {
+    session_id: &'a str,
+    digest: &'a str,
+}
+
+/// 원격 백업 서버에 업로드하는 클라이언트. 하나의 백업 세션 동안 업로드한
+/// 바이트 전체에 대한 누적 해시(`session_digest`)를 유지해, 서버 쪽에서
+/// 세션이 중간에 잘리지 않았는지 `finalize_session`에서 확인할 수 있게 한다.
+pub struct RemoteBackupClient {
+    base_url: String,
+    client: reqwest::blocking::Client,
+    session_id: String,
+    session_digest: blake3::Hasher,
+}
+
+impl RemoteBackupClient {
+    /// `base_url`의 원격 저장소에 대한 새 업로드 세션을 연다. 세션 ID는
+    /// base_url과 생성 시각을 섞은 blake3 해시로 만들어, 같은 서버에 동시에
+    /// 여러 백업이 올라가도 섞이지 않게 한다.
+    pub fn new(base_url: impl Into<String>, started_at_secs: u64) -> Self {
+        let base_url = base_url.into();
+        let mut seed = blake3::Hasher::new();
+        seed.update(base_url.as_bytes());
+        seed.update(&started_at_secs.to_le_bytes());
+        let session_id = seed.finalize().to_hex().to_string();
+
+        Self {
+            base_url,
+            client: reqwest::blocking::Client::new(),
+            session_id,
+            session_digest: blake3::Hasher::new(),
+        }
+    }
+
+    /// `chunk_hashes` 중 서버가 이미 갖고 있는 해시 집합을 받아온다.
+    pub fn negotiate_known_chunks(&self, chunk_hashes: &[String]) -> Result<HashSet<String>> {
+        let url = format!("{}/api/known-chunks", self.base_url);
+        let request = KnownChunksRequest {
+            session_id: &self.session_id,
+            chunk_hashes,
+        };
+
+        let response: KnownChunksResponse = self.with_retry(|| {
+            self.client
+                .post(&url)
+                .json(&request)
+                .send()
+                .context("Sending known-chunks request")?
+                .error_for_status()
+                .context("Server rejected known-chunks request")?
+                .json()
+                .context("Parsing known-chunks response")
+        })?;
+
+        Ok(response.known_hashes.into_iter().collect())
+    }
+
+    /// `store`에서 읽은 청크들 중 서버가 모르는 것만 업로드한다. 업로드한
+    /// 청크 수를 반환한다.
+    pub fn upload_missing_chunks(&mut self, store: &ChunkStore, chunk_hashes: &[String]) -> Result<usize> {
+        let known = self.negotiate_known_chunks(chunk_hashes)?;
+        let missing: Vec<&String> = chunk_hashes.iter().filter(|h| !known.contains(*h)).collect();
+
+        for hash in &missing {
+            let data = store.read_chunk(hash)?;
+            self.upload_chunk(hash, &data)?;
+        }
+
+        Ok(missing.len())
+    }
+
+    fn upload_chunk(&mut self, hash: &str, data: &[u8]) -> Result<()> {
+        let url = format!("{}/api/chunks", self.base_url);
+        let request = UploadChunkRequest {
+            session_id: &self.session_id,
+            hash,
+            data,
+        };
+
+        self.with_retry(|| {
+            self.client
+                .post(&url)
+                .json(&request)
+                .send()
+                .context("Uploading chunk")?
+                .error_for_status()
+                .context("Server rejected chunk upload")
+                .map(|_| ())
+        })?;
+
+        self.session_digest.update(data);
+        Ok(())
+    }
+
+    /// 이 세션의 백업 매니페스트를 업로드한다. 복원 시 서버가 청크들을
+    /// 어떤 순서로 이어붙여야 하는지는 이 매니페스트가 담고 있다.
+    pub fn upload_manifest(&self, manifest: &BackupManifest) -> Result<()> {
+        let url = format!("{}/api/manifest", self.base_url);
+        self.with_retry(|| {
+            self.client
+                .post(&url)
+                .query(&[("session_id", self.session_id.as_str())])
+                .json(manifest)
+                .send()
+                .context("Uploading manifest")?
+                .error_for_status()
+                .context("Server rejected manifest upload")
+                .map(|_| ())
+        })
+    }
+
+    /// 세션을 마무리한다. 업로드한 전체 바이트에 대한 누적 다이제스트를
+    /// 함께 보내, 서버가 전송 중 잘림(truncation) 없이 모든 청크를
+    /// 받았는지 스스로 검증할 수 있게 한다.
+    pub fn finalize_session(self) -> Result<()> {
+        let digest = self.session_digest.finalize().to_hex().to_string();
+        let url = format!("{}/api/finalize", self.base_url);
+        let request = FinalizeSessionRequest {
+            session_id: &self.session_id,
+            digest: &digest,
+        };
+
+        self.with_retry(|| {
+            self.client
+                .post(&url)
+                .json(&request)
+                .send()
+                .context("Finalizing remote session")?
+                .error_for_status()
+                .context("Server rejected session finalize")
+                .map(|_| ())
+        })
+    }
+
+    /// `op`를 최대 `MAX_RETRIES`번 지수 백오프로 재시도한다.
+    fn with_retry<T>(&self, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            match op() {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt < MAX_RETRIES => {
+                    attempt += 1;
+                    thread::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1));
+                    let _ = err;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// 원격 서버에 올라간 백업을 내려받아 복원하는 쪽. `RemoteBackupClient`와
+/// 짝을 이루지만, 업로드 상태(세션 다이제스트)를 들고 있지 않아도 되므로
+/// 별도 타입으로 둔다.
+pub struct BackupReader {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl BackupReader {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// 세션의 백업 매니페스트를 가져온다.
+    pub fn fetch_manifest(&self, session_id: &str) -> Result<BackupManifest> {
+        let url = format!("{}/api/manifest", self.base_url);
+        let response = self.client
+            .get(&url)
+            .query(&[("session_id", session_id)])
+            .send()
+            .context("Fetching remote manifest")?
+            .error_for_status()
+            .context("Server rejected manifest fetch")?;
+        response.json().context("Parsing remote manifest")
+    }
+
+    /// 해시 하나에 해당하는 청크 원본 바이트를 가져온다.
+    pub fn fetch_chunk(&self, hash: &str) -> Result<Vec<u8>> {
+        let url = format!("{}/api/chunks/{}", self.base_url, hash);
+        let response = self.client
+            .get(&url)
+            .send()
+            .context("Fetching remote chunk")?
+            .error_for_status()
+            .context("Server rejected chunk fetch")?;
+        Ok(response.bytes().context("Reading remote chunk body")?.to_vec())
+    }
+
+    /// 원격 세션의 매니페스트와 청크를 모두 받아 `dest_dir` 아래에
+    /// 복원한다. 로컬 `ChunkStore::restore_from_manifest`와 같은 레이아웃을
+    /// 만들기 위해, 내려받은 청크는 `local_store`에 먼저 채워 넣은 뒤
+    /// 그 메서드에 위임한다.
+    pub fn restore(&self, session_id: &str, local_store: &ChunkStore, dest_dir: &std::path::Path) -> Result<usize> {
+        let manifest = self.fetch_manifest(session_id)?;
+
+        for chunks in manifest.files.values() {
+            for chunk in chunks {
+                if local_store.has_chunk(&chunk.hash) {
+                    continue;
+                }
+                let data = self.fetch_chunk(&chunk.hash)?;
+                local_store.store_fetched_chunk(&chunk.hash, &data)?;
+            }
+        }
+
+        let file_count = manifest.files.len();
+        local_store.restore_from_manifest(&manifest, dest_dir)?;
+        Ok(file_count)
+    }
+}
@@ -1,13 +1,15 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use blake3::Hasher;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::io::Read;
+use std::path::Path;
 use walkdir::WalkDir;
 
 use crate::config::AppConfig;
+use crate::store::ObjectStore;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotManifest {
@@ -19,8 +21,48 @@ pub struct SnapshotManifest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub rel_path: String,
+    /// Content size; `0` for anything that isn't `EntryKind::File`.
     pub size: u64,
+    /// Digest of the object holding this file's content; empty for
+    /// anything that isn't `EntryKind::File`.
     pub blake3: String,
+    /// Last modification time, as seconds since the Unix epoch.
+    pub mtime_unix: i64,
+    /// Sub-second remainder of `mtime_unix`, for nanosecond-accurate
+    /// restores.
+    #[serde(default)]
+    pub mtime_nanos: u32,
+    /// Unix permission bits (e.g. `0o644`).
+    pub mode: u32,
+    #[serde(default)]
+    pub uid: u32,
+    #[serde(default)]
+    pub gid: u32,
+    #[serde(default)]
+    pub kind: EntryKind,
+    /// Target path, when `kind == Symlink`.
+    #[serde(default)]
+    pub symlink_target: Option<String>,
+    /// `(major, minor)`, when `kind` is `BlockDevice`/`CharDevice`.
+    #[serde(default)]
+    pub device: Option<(u32, u32)>,
+    /// Extended attributes read via the `xattr` crate; empty for symlinks
+    /// (xattrs on the link itself, not its target, aren't captured).
+    #[serde(default)]
+    pub xattrs: HashMap<String, Vec<u8>>,
+}
+
+/// What kind of filesystem entry a `FileEntry` represents, so a restore can
+/// recreate it faithfully instead of only ever writing regular files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EntryKind {
+    #[default]
+    File,
+    Symlink,
+    Dir,
+    BlockDevice,
+    CharDevice,
+    Fifo,
 }
 
 fn hash_file(path: &Path) -> Result<(String, u64)> {
@@ -37,37 +79,196 @@ fn hash_file(path: &Path) -> Result<(String, u64)> {
     Ok((hasher.finalize().to_hex().to_string(), total))
 }
 
-fn ensure_dir(path: &Path) -> Result<()> {
-    fs::create_dir_all(path)?;
+/// Writes `bytes` under `digest` unless an object with that digest is
+/// already in `store` - the dedup check `run_backup`'s per-file loop relies
+/// on for both full and incremental backups.
+async fn write_object_if_missing(store: &dyn ObjectStore, digest: &str, bytes: &[u8]) -> Result<()> {
+    if !store.has_object(digest).await? {
+        store.put_object(digest, bytes).await?;
+    }
     Ok(())
 }
 
-fn object_path(store_dir: &Path, digest: &str) -> PathBuf {
-    let (dir, file) = digest.split_at(2);
-    store_dir.join("objects").join(dir).join(file)
+#[cfg(unix)]
+fn device_id(path: &Path) -> Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(fs::metadata(path)?.dev())
 }
 
-fn write_object_if_missing(store_dir: &Path, src: &Path, digest: &str) -> Result<()> {
-    let obj_path = object_path(store_dir, digest);
-    if obj_path.exists() { return Ok(()); }
-    if let Some(parent) = obj_path.parent() { ensure_dir(parent)?; }
-    fs::copy(src, &obj_path).with_context(|| format!("Copying {} to object {}", src.display(), obj_path.display()))?;
-    Ok(())
+#[cfg(unix)]
+fn read_xattrs(path: &Path) -> HashMap<String, Vec<u8>> {
+    let Ok(names) = xattr::list(path) else {
+        return HashMap::new();
+    };
+    names
+        .filter_map(|name| {
+            let value = xattr::get(path, &name).ok().flatten()?;
+            Some((name.to_string_lossy().to_string(), value))
+        })
+        .collect()
 }
 
-pub async fn run_backup(cfg: &AppConfig, source_dir: &Path, store_dir: &Path) -> Result<()> {
-    ensure_dir(store_dir)?;
-    ensure_dir(&store_dir.join("objects"))?;
-    ensure_dir(&store_dir.join("manifests"))?;
+/// Walks `source_dir` and writes a new self-contained manifest.
+///
+/// If `reference_snapshot` names a prior snapshot, its manifest is loaded
+/// and files whose relative path, size, and mtime are unchanged reuse the
+/// reference's `blake3` digest directly instead of being re-hashed and
+/// re-uploaded - only new or modified files pay the `hash_file` +
+/// `write_object_if_missing` cost.
+///
+/// If `same_device` is set, the traversal records `source_dir`'s `st_dev`
+/// and refuses to descend into entries on a different device, so network
+/// or bind mounts under `source_dir` aren't silently swept into the backup.
+pub async fn run_backup(
+    _cfg: &AppConfig,
+    source_dir: &Path,
+    store: &dyn ObjectStore,
+    reference_snapshot: Option<&str>,
+    same_device: bool,
+) -> Result<()> {
+    let reference = match reference_snapshot {
+        Some(id) => Some(store.get_manifest(id).await?),
+        None => None,
+    };
+    let reference_by_path: HashMap<&str, &FileEntry> = reference
+        .as_ref()
+        .map(|m| m.files.iter().map(|f| (f.rel_path.as_str(), f)).collect())
+        .unwrap_or_default();
+
+    #[cfg(unix)]
+    let source_dev = same_device.then(|| device_id(source_dir)).transpose()?;
+
+    let walker = WalkDir::new(source_dir).follow_links(false).into_iter().filter_entry(|entry| {
+        #[cfg(unix)]
+        if let Some(dev) = source_dev {
+            use std::os::unix::fs::MetadataExt;
+            if entry.metadata().map(|m| m.dev() != dev).unwrap_or(false) {
+                return false;
+            }
+        }
+        true
+    });
 
     let mut entries: Vec<FileEntry> = Vec::new();
-    for entry in WalkDir::new(source_dir).follow_links(false).into_iter().filter_map(|e| e.ok()) {
-        if !entry.file_type().is_file() { continue; }
+    for entry in walker.filter_map(|e| e.ok()) {
         let path = entry.path();
+        if entry.depth() == 0 {
+            // The root itself isn't recorded; restore creates `target_dir`
+            // directly.
+            continue;
+        }
         let rel = path.strip_prefix(source_dir).unwrap().to_string_lossy().to_string();
-        let (digest, size) = hash_file(path)?;
-        write_object_if_missing(store_dir, path, &digest)?;
-        entries.push(FileEntry { rel_path: rel, size, blake3: digest });
+
+        // `symlink_metadata` so symlinks are captured as themselves, not
+        // followed through to whatever they point at.
+        let meta = path.symlink_metadata()?;
+        let mtime_unix = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        #[cfg(unix)]
+        let (kind, mode, uid, gid, mtime_nanos, symlink_target, device, xattrs) = {
+            use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+            let file_type = meta.file_type();
+            let kind = if file_type.is_symlink() {
+                EntryKind::Symlink
+            } else if file_type.is_dir() {
+                EntryKind::Dir
+            } else if file_type.is_block_device() {
+                EntryKind::BlockDevice
+            } else if file_type.is_char_device() {
+                EntryKind::CharDevice
+            } else if file_type.is_fifo() {
+                EntryKind::Fifo
+            } else {
+                EntryKind::File
+            };
+
+            let symlink_target = (kind == EntryKind::Symlink)
+                .then(|| fs::read_link(path).ok())
+                .flatten()
+                .map(|t| t.to_string_lossy().to_string());
+            let device = matches!(kind, EntryKind::BlockDevice | EntryKind::CharDevice)
+                .then(|| {
+                    let rdev = meta.rdev();
+                    // SAFETY: `major`/`minor` just decompose the `dev_t` the
+                    // above `rdev()` already returned; no pointers involved.
+                    unsafe { (libc::major(rdev), libc::minor(rdev)) }
+                });
+            let xattrs = if kind == EntryKind::Symlink { HashMap::new() } else { read_xattrs(path) };
+
+            (
+                kind,
+                meta.permissions().mode(),
+                meta.uid(),
+                meta.gid(),
+                meta.mtime_nsec() as u32,
+                symlink_target,
+                device,
+                xattrs,
+            )
+        };
+        #[cfg(not(unix))]
+        let (kind, mode, uid, gid, mtime_nanos, symlink_target, device, xattrs) = (
+            if meta.is_dir() { EntryKind::Dir } else { EntryKind::File },
+            0o644,
+            0,
+            0,
+            0,
+            None,
+            None,
+            HashMap::new(),
+        );
+
+        if !matches!(kind, EntryKind::File) {
+            entries.push(FileEntry {
+                rel_path: rel,
+                size: 0,
+                blake3: String::new(),
+                mtime_unix,
+                mtime_nanos,
+                mode,
+                uid,
+                gid,
+                kind,
+                symlink_target,
+                device,
+                xattrs,
+            });
+            continue;
+        }
+
+        let size = meta.len();
+        let unchanged = reference_by_path
+            .get(rel.as_str())
+            .filter(|prev| prev.size == size && prev.mtime_unix == mtime_unix);
+
+        let digest = if let Some(prev) = unchanged {
+            prev.blake3.clone()
+        } else {
+            let (digest, _) = hash_file(path)?;
+            let bytes = fs::read(path)?;
+            write_object_if_missing(store, &digest, &bytes).await?;
+            digest
+        };
+
+        entries.push(FileEntry {
+            rel_path: rel,
+            size,
+            blake3: digest,
+            mtime_unix,
+            mtime_nanos,
+            mode,
+            uid,
+            gid,
+            kind,
+            symlink_target,
+            device,
+            xattrs,
+        });
     }
 
     let snapshot_id = Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string();
@@ -76,12 +277,8 @@ pub async fn run_backup(cfg: &AppConfig, source_dir: &Path, store_dir: &Path) ->
         created_at_utc: Utc::now().to_rfc3339(),
         files: entries,
     };
-    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
-    let manifest_path = store_dir.join("manifests").join(format!("{}.json", snapshot_id));
-    let mut f = fs::File::create(&manifest_path)?;
-    f.write_all(&manifest_json)?;
-    f.flush()?;
-    println!("Snapshot created: {}", manifest_path.display());
+    store.put_manifest(&manifest).await?;
+    println!("Snapshot created: {}", snapshot_id);
     Ok(())
 }
 
@@ -8,6 +8,9 @@ mod backup;
 mod restore;
 mod monitor;
 mod ai;
+mod store;
+mod mount;
+mod prune;
 
 use crate::config::AppConfig;
 
@@ -63,6 +66,16 @@ enum Commands {
         /// Store directory (overrides config)
         #[arg(short, long)]
         store: Option<PathBuf>,
+        /// Object store URL, e.g. `file:///path` or `s3://bucket?region=...` (overrides --store)
+        #[arg(long = "store-url")]
+        store_url: Option<String>,
+        /// Prior snapshot id to diff against: unchanged files (same path,
+        /// size, mtime) are skipped instead of re-hashed and re-uploaded
+        #[arg(long)]
+        reference: Option<String>,
+        /// Refuse to descend into mount points other than source_dir's own
+        #[arg(long = "xdev")]
+        same_device: bool,
     },
     /// Restore latest snapshot
     Restore {
@@ -72,6 +85,9 @@ enum Commands {
         /// Store directory (overrides config)
         #[arg(short, long)]
         store: Option<PathBuf>,
+        /// Object store URL, e.g. `file:///path` or `s3://bucket?region=...` (overrides --store)
+        #[arg(long = "store-url")]
+        store_url: Option<String>,
     },
     /// Monitor filesystem and consult AI heuristics
     Monitor {
@@ -79,10 +95,52 @@ enum Commands {
         #[arg(short, long)]
         watch: Option<PathBuf>,
     },
+    /// Mount a snapshot as a read-only FUSE filesystem
+    Mount {
+        /// Directory to mount the snapshot at
+        mountpoint: PathBuf,
+        /// Snapshot id to mount (defaults to the latest)
+        #[arg(long)]
+        snapshot: Option<String>,
+        /// Store directory (overrides config)
+        #[arg(short, long)]
+        store: Option<PathBuf>,
+        /// Object store URL, e.g. `file:///path` or `s3://bucket?region=...` (overrides --store)
+        #[arg(long = "store-url")]
+        store_url: Option<String>,
+    },
+    /// Delete snapshots and unreferenced chunks to reclaim space
+    Prune {
+        /// Keep only the N most recent snapshots
+        #[arg(long = "keep-last", conflicts_with = "keep_within")]
+        keep_last: Option<usize>,
+        /// Keep only snapshots newer than this (e.g. "30d", "24h")
+        #[arg(long = "keep-within")]
+        keep_within: Option<String>,
+        /// Report what would be deleted without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Store directory (overrides config)
+        #[arg(short, long)]
+        store: Option<PathBuf>,
+        /// Object store URL, e.g. `file:///path` or `s3://bucket?region=...` (overrides --store)
+        #[arg(long = "store-url")]
+        store_url: Option<String>,
+    },
     /// Print resolved configuration
     Config,
 }
 
+/// Resolve the object store to use for a Backup/Restore invocation:
+/// an explicit `--store-url` wins, otherwise fall back to `file://<store dir>`
+/// built from `--store` or the config default.
+fn resolve_store_url(store_url: Option<String>, store_dir: Option<PathBuf>, cfg: &AppConfig) -> String {
+    store_url.unwrap_or_else(|| {
+        let dir = store_dir.unwrap_or_else(|| cfg.store_dir.clone());
+        format!("file://{}", dir.display())
+    })
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -116,20 +174,54 @@ async fn main() -> Result<()> {
             crypto::decrypt_file(&key_path, &input, &out_path)?;
             println!("Decrypted: {} -> {}", input.display(), out_path.display());
         }
-        Commands::Backup { source, store } => {
+        Commands::Backup { source, store, store_url, reference, same_device } => {
             let src = source.unwrap_or_else(|| cfg.source_dir.clone());
-            let sto = store.unwrap_or_else(|| cfg.store_dir.clone());
-            backup::run_backup(&cfg, &src, &sto).await?;
+            let url = resolve_store_url(store_url, store, &cfg);
+            let object_store = store::open_store(&url).await?;
+            backup::run_backup(&cfg, &src, object_store.as_ref(), reference.as_deref(), same_device).await?;
         }
-        Commands::Restore { target, store } => {
+        Commands::Restore { target, store, store_url } => {
             let tgt = target.unwrap_or_else(|| cfg.restore_dir.clone());
-            let sto = store.unwrap_or_else(|| cfg.store_dir.clone());
-            restore::run_restore(&cfg, &sto, &tgt).await?;
+            let url = resolve_store_url(store_url, store, &cfg);
+            let object_store = store::open_store(&url).await?;
+            restore::run_restore(&cfg, object_store.as_ref(), &tgt).await?;
         }
         Commands::Monitor { watch } => {
             let dir = watch.unwrap_or_else(|| cfg.source_dir.clone());
             monitor::run_monitor(&cfg, &dir).await?;
         }
+        Commands::Mount { mountpoint, snapshot, store, store_url } => {
+            let url = resolve_store_url(store_url, store, &cfg);
+            let object_store: std::sync::Arc<dyn store::ObjectStore> = store::open_store(&url).await?.into();
+            mount::run_mount(object_store, snapshot.as_deref(), &mountpoint).await?;
+        }
+        Commands::Prune { keep_last, keep_within, dry_run, store, store_url } => {
+            let url = resolve_store_url(store_url, store, &cfg);
+            let object_store = store::open_store(&url).await?;
+            let keep = match (keep_last, keep_within) {
+                (Some(n), None) => prune::KeepPolicy::Last(n),
+                (None, Some(d)) => prune::KeepPolicy::Within(prune::parse_duration(&d)?),
+                _ => anyhow::bail!("Specify exactly one of --keep-last or --keep-within"),
+            };
+            let report = prune::run_prune(object_store.as_ref(), keep, dry_run).await?;
+            if dry_run {
+                println!(
+                    "Would remove {} manifest(s) and {} object(s), reclaiming {} bytes (keeping {})",
+                    report.removed_manifests.len(),
+                    report.removed_objects,
+                    report.reclaimed_bytes,
+                    report.kept_manifests.len()
+                );
+            } else {
+                println!(
+                    "Removed {} manifest(s) and {} object(s), reclaimed {} bytes (kept {})",
+                    report.removed_manifests.len(),
+                    report.removed_objects,
+                    report.reclaimed_bytes,
+                    report.kept_manifests.len()
+                );
+            }
+        }
         Commands::Config => {
             println!("{}", serde_json::to_string_pretty(&cfg)?);
         }
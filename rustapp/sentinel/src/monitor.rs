@@ -3,12 +3,22 @@ use crate::config::AppConfig;
 use anyhow::Result;
 use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use reqwest::Client;
-use std::collections::VecDeque;
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use sysinfo::System;
 use tokio::sync::mpsc;
 
+/// How far back `TouchWindow` looks when computing `unique_ext_ratio`. Wider
+/// than `RateWindow`'s one second so the ratio has enough samples to be
+/// meaningful instead of flapping between 0.0 and 1.0.
+const TOUCH_WINDOW: Duration = Duration::from_secs(5);
+/// Bytes sampled from the start of a modified file to estimate its entropy.
+const ENTROPY_SAMPLE_BYTES: usize = 8192;
+
 struct RateWindow {
     timestamps: VecDeque<Instant>,
 }
@@ -27,6 +37,130 @@ impl RateWindow {
     }
 }
 
+/// Tracks recently touched paths to compute `unique_ext_ratio`: distinct
+/// file extensions over total touches in `TOUCH_WINDOW`. Ransomware rewriting
+/// everything to a single new extension (e.g. `.locked`) collapses this
+/// toward `1 / touches`, while ordinary activity across many file types sits
+/// much higher.
+struct TouchWindow {
+    touches: VecDeque<(Instant, String)>,
+}
+
+impl TouchWindow {
+    fn new() -> Self { Self { touches: VecDeque::new() } }
+
+    fn push(&mut self, now: Instant, path: &Path) {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.touches.push_back((now, ext));
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some((ts, _)) = self.touches.front() {
+            if now.duration_since(*ts) > TOUCH_WINDOW { self.touches.pop_front(); } else { break; }
+        }
+    }
+
+    fn unique_ext_ratio(&mut self, now: Instant) -> f32 {
+        self.prune(now);
+        if self.touches.is_empty() {
+            return 0.0;
+        }
+        let distinct: std::collections::HashSet<&str> =
+            self.touches.iter().map(|(_, ext)| ext.as_str()).collect();
+        distinct.len() as f32 / self.touches.len() as f32
+    }
+}
+
+/// Tracks the entropy of each recently modified file so we can report how
+/// much it jumped since we last saw it: encrypted/compressed output has
+/// near-maximal entropy versus typical plaintext, so a large positive delta
+/// is the classic ransomware signature.
+///
+/// Entries are pruned on every `observe` the same way `TouchWindow`/
+/// `RateWindow` prune theirs, so this stays bounded by `TOUCH_WINDOW` worth
+/// of distinct paths instead of growing for the lifetime of the process -
+/// which would otherwise be worst during exactly the mass-file-rewrite event
+/// this tracker exists to flag.
+struct EntropyTracker {
+    last_entropy: HashMap<PathBuf, (Instant, f32)>,
+    max_delta_since_tick: f32,
+}
+
+impl EntropyTracker {
+    fn new() -> Self {
+        Self { last_entropy: HashMap::new(), max_delta_since_tick: 0.0 }
+    }
+
+    fn prune(&mut self, now: Instant) {
+        self.last_entropy.retain(|_, (ts, _)| now.duration_since(*ts) <= TOUCH_WINDOW);
+    }
+
+    fn observe(&mut self, path: &Path) {
+        let Ok(entropy) = sample_entropy(path) else { return };
+        let now = Instant::now();
+        self.prune(now);
+        let delta = match self.last_entropy.get(path) {
+            Some((_, previous)) => (entropy - previous).abs(),
+            None => 0.0,
+        };
+        self.max_delta_since_tick = self.max_delta_since_tick.max(delta);
+        self.last_entropy.insert(path.to_path_buf(), (now, entropy));
+    }
+
+    fn take_delta(&mut self) -> f32 {
+        std::mem::replace(&mut self.max_delta_since_tick, 0.0)
+    }
+}
+
+fn sample_entropy(path: &Path) -> std::io::Result<f32> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0u8; ENTROPY_SAMPLE_BYTES];
+    let bytes_read = file.read(&mut buffer)?;
+    if bytes_read == 0 {
+        return Ok(0.0);
+    }
+
+    let mut frequency = [0u32; 256];
+    for &byte in &buffer[..bytes_read] {
+        frequency[byte as usize] += 1;
+    }
+
+    let total = bytes_read as f32;
+    let mut entropy = 0.0;
+    for &count in &frequency {
+        if count > 0 {
+            let p = count as f32 / total;
+            entropy -= p * p.log2();
+        }
+    }
+    Ok(entropy)
+}
+
+/// Samples the live process count so `process_count_delta` can flag a burst
+/// of new processes (e.g. the ransomware binary plus helper/encryption
+/// worker processes it spawns) between AI ticks.
+struct ProcessCountTracker {
+    system: System,
+    last_count: Option<i64>,
+}
+
+impl ProcessCountTracker {
+    fn new() -> Self {
+        Self { system: System::new(), last_count: None }
+    }
+
+    fn sample_delta(&mut self) -> f32 {
+        self.system.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+        let count = self.system.processes().len() as i64;
+        let delta = self.last_count.map(|prev| (count - prev) as f32).unwrap_or(0.0);
+        self.last_count = Some(count);
+        delta
+    }
+}
+
 pub async fn run_monitor(cfg: &AppConfig, watch_dir: &Path) -> Result<()> {
     let client = Client::new();
     let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
@@ -47,20 +181,29 @@ pub async fn run_monitor(cfg: &AppConfig, watch_dir: &Path) -> Result<()> {
     println!("Monitoring {}...", watch_dir.display());
     let mut last_ai = Instant::now();
     let mut last_action: Option<String> = None;
+    let mut touches = TouchWindow::new();
+    let mut entropy = EntropyTracker::new();
+    let mut processes = ProcessCountTracker::new();
 
     while let Some(event) = rx.recv().await {
         let now = Instant::now();
         if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
             if let Ok(mut rw) = window.lock() { rw.push(now); }
+            for path in &event.paths {
+                touches.push(now, path);
+                if path.is_file() {
+                    entropy.observe(path);
+                }
+            }
         }
 
         if now.duration_since(last_ai) >= Duration::from_millis(500) {
             let rate = if let Ok(mut rw) = window.lock() { rw.per_second(now) } else { 0.0 };
             let features = Features {
                 file_write_rate_per_sec: rate,
-                unique_ext_ratio: 0.0,
-                entropy_delta: 0.0,
-                process_count_delta: 0.0,
+                unique_ext_ratio: touches.unique_ext_ratio(now),
+                entropy_delta: entropy.take_delta(),
+                process_count_delta: processes.sample_delta(),
             };
             match query_ai(&client, &cfg.ai_url, &features).await {
                 Ok(AiResponse { risk_score, action }) => {
@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request,
+};
+use lru::LruCache;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::backup::{EntryKind, FileEntry, SnapshotManifest};
+use crate::store::ObjectStore;
+
+const TTL: Duration = Duration::from_secs(1);
+const ROOT_INODE: u64 = 1;
+
+/// Cap on how many whole-object buffers `read()` keeps around at once, so
+/// repeated page-sized reads of a large file don't re-fetch it from the
+/// store every time, without unbounded memory growth from browsing many
+/// files in one mount session.
+const OBJECT_CACHE_CAPACITY: usize = 64;
+
+enum Node {
+    Dir { children: HashMap<String, u64> },
+    File { entry: FileEntry },
+}
+
+/// Read-only FUSE filesystem over a single `SnapshotManifest`. Directories
+/// and files come entirely from the manifest's `rel_path` entries; file
+/// content is fetched lazily from the backing `ObjectStore` on `read` and
+/// kept in `object_cache` so subsequent reads of the same file are served
+/// from memory instead of hitting the store again.
+struct SnapshotFs {
+    nodes: HashMap<u64, Node>,
+    rt: tokio::runtime::Handle,
+    store: Arc<dyn ObjectStore>,
+    object_cache: LruCache<String, Vec<u8>>,
+}
+
+fn split_components(rel_path: &str) -> Vec<&str> {
+    rel_path.split('/').filter(|c| !c.is_empty()).collect()
+}
+
+impl SnapshotFs {
+    fn new(manifest: &SnapshotManifest, store: Arc<dyn ObjectStore>, rt: tokio::runtime::Handle) -> Self {
+        let mut nodes = HashMap::new();
+        nodes.insert(ROOT_INODE, Node::Dir { children: HashMap::new() });
+        let mut next_inode = ROOT_INODE + 1;
+
+        for file in &manifest.files {
+            // Non-regular entries (dirs, symlinks, device nodes, ...) aren't
+            // browsable yet; their parent directories still show up via the
+            // path components of whatever regular files they contain.
+            if file.kind != EntryKind::File {
+                continue;
+            }
+            let components = split_components(&file.rel_path);
+            if components.is_empty() {
+                continue;
+            }
+            let mut parent = ROOT_INODE;
+            for (idx, name) in components.iter().enumerate() {
+                let is_last = idx == components.len() - 1;
+                let existing = match nodes.get_mut(&parent).unwrap() {
+                    Node::Dir { children } => children.get(*name).copied(),
+                    Node::File { .. } => None,
+                };
+                let child_inode = if let Some(inode) = existing {
+                    inode
+                } else {
+                    let inode = next_inode;
+                    next_inode += 1;
+                    let node = if is_last {
+                        Node::File { entry: file.clone() }
+                    } else {
+                        Node::Dir { children: HashMap::new() }
+                    };
+                    nodes.insert(inode, node);
+                    if let Node::Dir { children } = nodes.get_mut(&parent).unwrap() {
+                        children.insert(name.to_string(), inode);
+                    }
+                    inode
+                };
+                parent = child_inode;
+            }
+        }
+
+        let object_cache = LruCache::new(NonZeroUsize::new(OBJECT_CACHE_CAPACITY).unwrap());
+        SnapshotFs { nodes, rt, store, object_cache }
+    }
+
+    fn attr_for(&self, inode: u64) -> Option<FileAttr> {
+        let node = self.nodes.get(&inode)?;
+        let now = UNIX_EPOCH; // Snapshots are immutable; times come from the entry itself.
+        Some(match node {
+            Node::Dir { .. } => FileAttr {
+                ino: inode,
+                size: 0,
+                blocks: 0,
+                atime: now,
+                mtime: now,
+                ctime: now,
+                crtime: now,
+                kind: FileType::Directory,
+                perm: 0o555,
+                nlink: 2,
+                uid: 0,
+                gid: 0,
+                rdev: 0,
+                blksize: 512,
+                flags: 0,
+            },
+            Node::File { entry } => {
+                let mtime = UNIX_EPOCH + Duration::from_secs(entry.mtime_unix.max(0) as u64);
+                FileAttr {
+                    ino: inode,
+                    size: entry.size,
+                    blocks: entry.size.div_ceil(512),
+                    atime: mtime,
+                    mtime,
+                    ctime: mtime,
+                    crtime: mtime,
+                    kind: FileType::RegularFile,
+                    perm: (entry.mode & 0o777) as u16,
+                    nlink: 1,
+                    uid: 0,
+                    gid: 0,
+                    rdev: 0,
+                    blksize: 512,
+                    flags: 0,
+                }
+            }
+        })
+    }
+}
+
+impl Filesystem for SnapshotFs {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let name = match name.to_str() {
+            Some(n) => n,
+            None => return reply.error(libc::EINVAL),
+        };
+        let child = match self.nodes.get(&parent) {
+            Some(Node::Dir { children }) => children.get(name).copied(),
+            _ => None,
+        };
+        match child.and_then(|inode| self.attr_for(inode)) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, inode: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.attr_for(inode) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let entry = match self.nodes.get(&inode) {
+            Some(Node::File { entry }) => entry.clone(),
+            _ => return reply.error(libc::ENOENT),
+        };
+
+        let data = if let Some(cached) = self.object_cache.get(&entry.blake3) {
+            cached.clone()
+        } else {
+            let store = self.store.clone();
+            let digest = entry.blake3.clone();
+            match self.rt.block_on(async move { store.get_object(&digest).await }) {
+                Ok(data) => {
+                    self.object_cache.put(entry.blake3.clone(), data.clone());
+                    data
+                }
+                Err(_) => return reply.error(libc::EIO),
+            }
+        };
+
+        let start = (offset as usize).min(data.len());
+        let end = (start + size as usize).min(data.len());
+        reply.data(&data[start..end]);
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let children: Vec<(String, u64, FileType)> = match self.nodes.get(&inode) {
+            Some(Node::Dir { children }) => children
+                .iter()
+                .map(|(name, child_inode)| {
+                    let kind = match self.nodes.get(child_inode) {
+                        Some(Node::Dir { .. }) => FileType::Directory,
+                        _ => FileType::RegularFile,
+                    };
+                    (name.clone(), *child_inode, kind)
+                })
+                .collect(),
+            _ => return reply.error(libc::ENOENT),
+        };
+
+        let mut entries = vec![
+            (inode, FileType::Directory, ".".to_string()),
+            (inode, FileType::Directory, "..".to_string()),
+        ];
+        entries.extend(children.into_iter().map(|(name, ino, kind)| (ino, kind, name)));
+
+        for (idx, (ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(ino, (idx + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mount `snapshot_id` (or the latest snapshot if `None`) from `store` as a
+/// read-only filesystem at `mountpoint`. Blocks until the mount is unmounted.
+pub async fn run_mount(store: Arc<dyn ObjectStore>, snapshot_id: Option<&str>, mountpoint: &Path) -> Result<()> {
+    let manifest = match snapshot_id {
+        Some(id) => store.get_manifest(id).await?,
+        None => {
+            let mut ids = store.list_manifests().await?;
+            let latest = ids.pop().context("No manifests found")?;
+            store.get_manifest(&latest).await?
+        }
+    };
+
+    let rt = tokio::runtime::Handle::current();
+    let mountpoint = mountpoint.to_path_buf();
+    let snapshot_label = manifest.snapshot_id.clone();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let fs = SnapshotFs::new(&manifest, store, rt);
+        let options = vec![MountOption::RO, MountOption::FSName("sentinel".to_string())];
+        fuser::mount2(fs, &mountpoint, &options)
+            .with_context(|| format!("Mounting snapshot at {}", mountpoint.display()))
+    })
+    .await
+    .context("Mount task panicked")??;
+
+    println!("Unmounted snapshot {}", snapshot_label);
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+use crate::store::ObjectStore;
+
+/// Which manifests to keep when pruning; mutually exclusive knobs mirroring
+/// `--keep-last N` / `--keep-within DURATION` on the CLI.
+pub enum KeepPolicy {
+    Last(usize),
+    Within(chrono::Duration),
+}
+
+/// Result of a prune run: what was (or would be) kept/removed, and how many
+/// bytes were (or would be) reclaimed. Populated even under `--dry-run`.
+#[derive(Debug)]
+pub struct PruneReport {
+    pub kept_manifests: Vec<String>,
+    pub removed_manifests: Vec<String>,
+    pub removed_objects: usize,
+    pub reclaimed_bytes: u64,
+}
+
+/// Parse a simple duration like `30d`, `24h`, `45m`, or `90s`.
+pub fn parse_duration(s: &str) -> Result<chrono::Duration> {
+    let s = s.trim();
+    let (amount, unit) = s.split_at(s.len().saturating_sub(1));
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid duration: {}", s))?;
+    match unit {
+        "d" => Ok(chrono::Duration::days(amount)),
+        "h" => Ok(chrono::Duration::hours(amount)),
+        "m" => Ok(chrono::Duration::minutes(amount)),
+        "s" => Ok(chrono::Duration::seconds(amount)),
+        _ => anyhow::bail!("Unsupported duration unit in {} (expected d/h/m/s)", s),
+    }
+}
+
+fn select_surviving_manifests(
+    manifest_ids: &[String],
+    created_at: &[DateTime<Utc>],
+    keep: &KeepPolicy,
+) -> HashSet<usize> {
+    match keep {
+        KeepPolicy::Last(n) => {
+            let start = manifest_ids.len().saturating_sub(*n);
+            (start..manifest_ids.len()).collect()
+        }
+        KeepPolicy::Within(duration) => {
+            let cutoff = Utc::now() - *duration;
+            created_at
+                .iter()
+                .enumerate()
+                .filter(|(_, created)| **created >= cutoff)
+                .map(|(idx, _)| idx)
+                .collect()
+        }
+    }
+}
+
+/// Mark-and-sweep GC over the chunk store: keep the manifests selected by
+/// `keep`, compute the set of object digests still reachable from them, and
+/// delete every object and dropped manifest not in that set. Under
+/// `dry_run`, nothing is deleted and the report describes what would be.
+pub async fn run_prune(store: &dyn ObjectStore, keep: KeepPolicy, dry_run: bool) -> Result<PruneReport> {
+    let manifest_ids = store.list_manifests().await?;
+    let mut manifests = Vec::with_capacity(manifest_ids.len());
+    for id in &manifest_ids {
+        manifests.push(store.get_manifest(id).await?);
+    }
+    let created_at: Vec<DateTime<Utc>> = manifests
+        .iter()
+        .map(|m| DateTime::parse_from_rfc3339(&m.created_at_utc).map(|d| d.with_timezone(&Utc)))
+        .collect::<Result<_, _>>()
+        .context("Parsing manifest created_at_utc")?;
+
+    let surviving = select_surviving_manifests(&manifest_ids, &created_at, &keep);
+
+    let mut referenced: HashSet<String> = HashSet::new();
+    let mut kept_manifests = Vec::new();
+    let mut removed_manifests = Vec::new();
+    for (idx, id) in manifest_ids.iter().enumerate() {
+        if surviving.contains(&idx) {
+            referenced.extend(manifests[idx].files.iter().map(|f| f.blake3.clone()));
+            kept_manifests.push(id.clone());
+        } else {
+            removed_manifests.push(id.clone());
+        }
+    }
+
+    let mut removed_objects = 0usize;
+    let mut reclaimed_bytes = 0u64;
+    for digest in store.list_objects().await? {
+        if referenced.contains(&digest) {
+            continue;
+        }
+        reclaimed_bytes += store.object_size(&digest).await?;
+        removed_objects += 1;
+        if !dry_run {
+            store.delete_object(&digest).await?;
+        }
+    }
+
+    if !dry_run {
+        for id in &removed_manifests {
+            store.delete_manifest(id).await?;
+        }
+    }
+
+    Ok(PruneReport {
+        kept_manifests,
+        removed_manifests,
+        removed_objects,
+        reclaimed_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_each_unit() {
+        assert_eq!(parse_duration("30d").unwrap(), chrono::Duration::days(30));
+        assert_eq!(parse_duration("24h").unwrap(), chrono::Duration::hours(24));
+        assert_eq!(parse_duration("45m").unwrap(), chrono::Duration::minutes(45));
+        assert_eq!(parse_duration("90s").unwrap(), chrono::Duration::seconds(90));
+    }
+
+    #[test]
+    fn parse_duration_rejects_missing_unit_suffix() {
+        assert!(parse_duration("30").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_unknown_unit() {
+        assert!(parse_duration("30w").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_non_numeric_amount() {
+        assert!(parse_duration("xd").is_err());
+    }
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("snap-{}", i)).collect()
+    }
+
+    #[test]
+    fn select_surviving_keep_last_under_count() {
+        let manifest_ids = ids(5);
+        let created_at = vec![Utc::now(); 5];
+        let surviving = select_surviving_manifests(&manifest_ids, &created_at, &KeepPolicy::Last(2));
+        assert_eq!(surviving, HashSet::from([3, 4]));
+    }
+
+    #[test]
+    fn select_surviving_keep_last_exceeding_count_keeps_all() {
+        let manifest_ids = ids(3);
+        let created_at = vec![Utc::now(); 3];
+        let surviving = select_surviving_manifests(&manifest_ids, &created_at, &KeepPolicy::Last(10));
+        assert_eq!(surviving, HashSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn select_surviving_keep_last_zero_keeps_none() {
+        let manifest_ids = ids(3);
+        let created_at = vec![Utc::now(); 3];
+        let surviving = select_surviving_manifests(&manifest_ids, &created_at, &KeepPolicy::Last(0));
+        assert!(surviving.is_empty());
+    }
+
+    #[test]
+    fn select_surviving_keep_within_filters_by_cutoff() {
+        let manifest_ids = ids(3);
+        let created_at = vec![
+            Utc::now() - chrono::Duration::days(10),
+            Utc::now() - chrono::Duration::hours(1),
+            Utc::now(),
+        ];
+        let surviving =
+            select_surviving_manifests(&manifest_ids, &created_at, &KeepPolicy::Within(chrono::Duration::days(1)));
+        assert_eq!(surviving, HashSet::from([1, 2]));
+    }
+}
@@ -1,39 +1,165 @@
 use anyhow::{Context, Result};
-use serde::Deserialize;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
-use crate::backup::SnapshotManifest;
+use crate::backup::{EntryKind, FileEntry};
 use crate::config::AppConfig;
+use crate::store::ObjectStore;
 
-fn object_path(store_dir: &Path, digest: &str) -> PathBuf {
-    let (dir, file) = digest.split_at(2);
-    store_dir.join("objects").join(dir).join(file)
+async fn latest_manifest(store: &dyn ObjectStore) -> Result<crate::backup::SnapshotManifest> {
+    let mut ids = store.list_manifests().await?;
+    let latest = ids.pop().ok_or_else(|| anyhow::anyhow!("No manifests found"))?;
+    store.get_manifest(&latest).await
 }
 
-fn latest_manifest_path(store_dir: &Path) -> Result<PathBuf> {
-    let mut manifests: Vec<PathBuf> = fs::read_dir(store_dir.join("manifests"))?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
-        .collect();
-    manifests.sort();
-    manifests.last().cloned().ok_or_else(|| anyhow::anyhow!("No manifests found"))
-}
-
-pub async fn run_restore(_cfg: &AppConfig, store_dir: &Path, target_dir: &Path) -> Result<()> {
+pub async fn run_restore(_cfg: &AppConfig, store: &dyn ObjectStore, target_dir: &Path) -> Result<()> {
     fs::create_dir_all(target_dir)?;
-    let manifest_path = latest_manifest_path(store_dir)?;
-    let data = fs::read(&manifest_path)?;
-    let manifest: SnapshotManifest = serde_json::from_slice(&data)?;
+    let manifest = latest_manifest(store).await?;
+    let mut pending_dirs: Vec<&FileEntry> = Vec::new();
 
     for file in manifest.files.iter() {
-        let src_obj = object_path(store_dir, &file.blake3);
         let dst_path = target_dir.join(&file.rel_path);
         if let Some(parent) = dst_path.parent() { fs::create_dir_all(parent)?; }
-        fs::copy(&src_obj, &dst_path).with_context(|| format!("Copy {} -> {}", src_obj.display(), dst_path.display()))?;
+
+        match file.kind {
+            EntryKind::Dir => {
+                fs::create_dir_all(&dst_path).with_context(|| format!("Creating dir {}", dst_path.display()))?;
+            }
+            EntryKind::Symlink => {
+                let target = file
+                    .symlink_target
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Symlink entry {} missing target", file.rel_path))?;
+                if dst_path.symlink_metadata().is_ok() {
+                    fs::remove_file(&dst_path)?;
+                }
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(target, &dst_path)
+                    .with_context(|| format!("Creating symlink {}", dst_path.display()))?;
+            }
+            EntryKind::Fifo => {
+                #[cfg(unix)]
+                mkfifo(&dst_path, file.mode)?;
+            }
+            EntryKind::BlockDevice | EntryKind::CharDevice => {
+                #[cfg(unix)]
+                {
+                    let (major, minor) = file
+                        .device
+                        .ok_or_else(|| anyhow::anyhow!("Device entry {} missing major/minor", file.rel_path))?;
+                    mknod_device(&dst_path, file.kind, major, minor, file.mode)?;
+                }
+            }
+            EntryKind::File => {
+                let bytes = store
+                    .get_object(&file.blake3)
+                    .await
+                    .with_context(|| format!("Fetching object {} for {}", file.blake3, file.rel_path))?;
+                fs::write(&dst_path, bytes).with_context(|| format!("Writing {}", dst_path.display()))?;
+            }
+        }
+
+        // Directories get their metadata applied after every entry has
+        // been restored (see below) - a restrictive mode like `0o555` or
+        // a preserved mtime would otherwise be clobbered by, or block,
+        // writes for the children still to come.
+        if file.kind == EntryKind::Dir {
+            pending_dirs.push(file);
+        } else {
+            #[cfg(unix)]
+            apply_metadata(&dst_path, file)?;
+        }
     }
+
+    // Deepest directories first, mirroring tar/rsync restore ordering, so a
+    // directory's permissions/mtime are applied only once every entry
+    // inside it - including nested subdirectories - already exists.
+    #[cfg(unix)]
+    for dir in pending_dirs.iter().rev() {
+        let dst_path = target_dir.join(&dir.rel_path);
+        apply_metadata(&dst_path, dir)?;
+    }
+
     println!("Restored snapshot {} to {}", manifest.snapshot_id, target_dir.display());
     Ok(())
 }
 
+/// Re-applies ownership, permissions, mtime, and xattrs captured at backup
+/// time. Ownership uses `lchown` (doesn't follow symlinks) so it's safe to
+/// run unconditionally; mode/mtime are skipped for symlinks since most
+/// filesystems don't support per-link permissions or timestamps.
+#[cfg(unix)]
+fn apply_metadata(path: &Path, entry: &FileEntry) -> Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+
+    // SAFETY: `c_path` is a valid NUL-terminated path for the duration of
+    // this call.
+    let rc = unsafe { libc::lchown(c_path.as_ptr(), entry.uid, entry.gid) };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("lchown {}", path.display()));
+    }
+
+    if entry.kind != EntryKind::Symlink {
+        fs::set_permissions(path, fs::Permissions::from_mode(entry.mode))
+            .with_context(|| format!("chmod {}", path.display()))?;
+
+        let ts = libc::timespec { tv_sec: entry.mtime_unix, tv_nsec: entry.mtime_nanos as i64 };
+        let specs = [ts, ts]; // atime, mtime
+        // SAFETY: `c_path` is valid; `specs` has exactly the 2 entries
+        // `utimensat` requires.
+        let rc = unsafe { libc::utimensat(libc::AT_FDCWD, c_path.as_ptr(), specs.as_ptr(), 0) };
+        if rc != 0 {
+            return Err(std::io::Error::last_os_error()).with_context(|| format!("utimensat {}", path.display()));
+        }
+    }
+
+    for (name, value) in &entry.xattrs {
+        xattr::set(path, name, value).with_context(|| format!("Setting xattr {} on {}", name, path.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mkfifo(path: &Path, mode: u32) -> Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is a valid NUL-terminated path for the duration of
+    // this call.
+    let rc = unsafe { libc::mkfifo(c_path.as_ptr(), mode as libc::mode_t) };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("mkfifo {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Requires `CAP_MKNOD` (or root) on most systems.
+#[cfg(unix)]
+fn mknod_device(path: &Path, kind: EntryKind, major: u32, minor: u32, mode: u32) -> Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let type_bits = match kind {
+        EntryKind::BlockDevice => libc::S_IFBLK,
+        EntryKind::CharDevice => libc::S_IFCHR,
+        _ => unreachable!("mknod_device only called for device entries"),
+    };
+    // SAFETY: `major`/`minor` just compose the `dev_t` value; no pointers
+    // involved.
+    let dev = unsafe { libc::makedev(major, minor) };
+    // SAFETY: `c_path` is a valid NUL-terminated path for the duration of
+    // this call.
+    let rc = unsafe { libc::mknod(c_path.as_ptr(), type_bits | (mode as libc::mode_t), dev) };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("mknod {}", path.display()));
+    }
+    Ok(())
+}
+
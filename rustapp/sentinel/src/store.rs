@@ -0,0 +1,357 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+
+use crate::backup::SnapshotManifest;
+
+/// Storage backend for content-addressed objects and snapshot manifests.
+///
+/// `run_backup`/`run_restore` operate against `dyn ObjectStore` so a
+/// snapshot can land on the local disk or be pushed straight to an
+/// off-host bucket, keeping it out of reach of ransomware running on the
+/// monitored host.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put_object(&self, digest: &str, bytes: &[u8]) -> Result<()>;
+    async fn get_object(&self, digest: &str) -> Result<Vec<u8>>;
+    async fn has_object(&self, digest: &str) -> Result<bool>;
+    /// Size in bytes of the stored object, for reclaimable-space reporting.
+    async fn object_size(&self, digest: &str) -> Result<u64>;
+    /// All object digests currently in the store, for GC mark-and-sweep.
+    async fn list_objects(&self) -> Result<Vec<String>>;
+    async fn delete_object(&self, digest: &str) -> Result<()>;
+
+    async fn put_manifest(&self, manifest: &SnapshotManifest) -> Result<()>;
+    async fn list_manifests(&self) -> Result<Vec<String>>;
+    async fn get_manifest(&self, snapshot_id: &str) -> Result<SnapshotManifest>;
+    async fn delete_manifest(&self, snapshot_id: &str) -> Result<()>;
+}
+
+fn object_rel_path(digest: &str) -> PathBuf {
+    let (dir, file) = digest.split_at(2);
+    PathBuf::from(dir).join(file)
+}
+
+/// Today's on-disk layout: `objects/<2>/<rest>` and `manifests/*.json`.
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: PathBuf) -> Self {
+        LocalStore { root }
+    }
+
+    fn object_path(&self, digest: &str) -> PathBuf {
+        self.root.join("objects").join(object_rel_path(digest))
+    }
+
+    fn manifest_path(&self, snapshot_id: &str) -> PathBuf {
+        self.root.join("manifests").join(format!("{}.json", snapshot_id))
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalStore {
+    async fn put_object(&self, digest: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.object_path(digest);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, bytes).await?;
+        Ok(())
+    }
+
+    async fn get_object(&self, digest: &str) -> Result<Vec<u8>> {
+        let path = self.object_path(digest);
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Reading object {}", path.display()))
+    }
+
+    async fn has_object(&self, digest: &str) -> Result<bool> {
+        Ok(self.object_path(digest).exists())
+    }
+
+    async fn object_size(&self, digest: &str) -> Result<u64> {
+        let path = self.object_path(digest);
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .with_context(|| format!("Stat-ing object {}", path.display()))?;
+        Ok(metadata.len())
+    }
+
+    async fn list_objects(&self) -> Result<Vec<String>> {
+        let objects_dir = self.root.join("objects");
+        let mut digests = Vec::new();
+        let mut prefixes = match tokio::fs::read_dir(&objects_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(digests),
+            Err(e) => return Err(e).context("Reading objects directory"),
+        };
+        while let Some(prefix) = prefixes.next_entry().await? {
+            let mut entries = tokio::fs::read_dir(prefix.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                if let Some(name) = entry.file_name().to_str() {
+                    let prefix_name = prefix.file_name().to_string_lossy().to_string();
+                    digests.push(format!("{}{}", prefix_name, name));
+                }
+            }
+        }
+        Ok(digests)
+    }
+
+    async fn delete_object(&self, digest: &str) -> Result<()> {
+        tokio::fs::remove_file(self.object_path(digest)).await?;
+        Ok(())
+    }
+
+    async fn put_manifest(&self, manifest: &SnapshotManifest) -> Result<()> {
+        let path = self.manifest_path(&manifest.snapshot_id);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let json = serde_json::to_vec_pretty(manifest)?;
+        tokio::fs::write(&path, json).await?;
+        Ok(())
+    }
+
+    async fn list_manifests(&self) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        let mut entries = tokio::fs::read_dir(self.root.join("manifests")).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    ids.push(stem.to_string());
+                }
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    async fn get_manifest(&self, snapshot_id: &str) -> Result<SnapshotManifest> {
+        let path = self.manifest_path(snapshot_id);
+        let data = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Reading manifest {}", path.display()))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    async fn delete_manifest(&self, snapshot_id: &str) -> Result<()> {
+        tokio::fs::remove_file(self.manifest_path(snapshot_id)).await?;
+        Ok(())
+    }
+}
+
+/// S3/Garage-compatible object store, so snapshots can live off-host.
+/// Bucket, region and an optional endpoint come from the `--store-url`
+/// (see [`open_store`]). Credentials are not set here; the client takes
+/// them from the AWS SDK's default credential chain.
+pub struct S3Store {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+impl S3Store {
+    pub async fn new(endpoint: Option<&str>, bucket: &str, region: &str) -> Result<Self> {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest()).region(
+            aws_config::Region::new(region.to_string()),
+        );
+        if let Some(endpoint) = endpoint {
+            loader = loader.endpoint_url(endpoint);
+        }
+        let config = loader.load().await;
+        let client = aws_sdk_s3::Client::new(&config);
+        Ok(S3Store {
+            client,
+            bucket: bucket.to_string(),
+        })
+    }
+
+    fn object_key(&self, digest: &str) -> String {
+        format!("objects/{}", object_rel_path(digest).display())
+    }
+
+    fn manifest_key(&self, snapshot_id: &str) -> String {
+        format!("manifests/{}.json", snapshot_id)
+    }
+
+    /// Pages through every object under `prefix`, since S3 caps a single
+    /// `ListObjectsV2` response at 1000 keys by default - returning just the
+    /// first page would silently drop anything past it, which `run_prune`
+    /// relies on being complete for both the reachable-set and the sweep.
+    async fn list_all_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let mut req = self.client.list_objects_v2().bucket(&self.bucket).prefix(prefix);
+            if let Some(token) = &continuation_token {
+                req = req.continuation_token(token);
+            }
+            let resp = req.send().await.context("S3 list_objects_v2 failed")?;
+
+            keys.extend(resp.contents().iter().filter_map(|obj| obj.key()).map(|key| key.to_string()));
+
+            if !resp.is_truncated().unwrap_or(false) {
+                break;
+            }
+            continuation_token = resp.next_continuation_token().map(|t| t.to_string());
+        }
+
+        Ok(keys)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    async fn put_object(&self, digest: &str, bytes: &[u8]) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(digest))
+            .body(bytes.to_vec().into())
+            .send()
+            .await
+            .context("S3 put_object failed")?;
+        Ok(())
+    }
+
+    async fn get_object(&self, digest: &str) -> Result<Vec<u8>> {
+        let resp = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(digest))
+            .send()
+            .await
+            .context("S3 get_object failed")?;
+        let bytes = resp.body.collect().await.context("Reading S3 object body")?;
+        Ok(bytes.into_bytes().to_vec())
+    }
+
+    async fn has_object(&self, digest: &str) -> Result<bool> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(digest))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().map(|e| e.is_not_found()).unwrap_or(false) => Ok(false),
+            Err(e) => Err(e).context("S3 head_object failed"),
+        }
+    }
+
+    async fn object_size(&self, digest: &str) -> Result<u64> {
+        let resp = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(digest))
+            .send()
+            .await
+            .context("S3 head_object failed")?;
+        Ok(resp.content_length().unwrap_or(0) as u64)
+    }
+
+    async fn list_objects(&self) -> Result<Vec<String>> {
+        Ok(self
+            .list_all_keys("objects/")
+            .await?
+            .iter()
+            .filter_map(|key| key.strip_prefix("objects/"))
+            .map(|rest| rest.replace('/', ""))
+            .collect())
+    }
+
+    async fn delete_object(&self, digest: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(digest))
+            .send()
+            .await
+            .context("S3 delete_object failed")?;
+        Ok(())
+    }
+
+    async fn put_manifest(&self, manifest: &SnapshotManifest) -> Result<()> {
+        let json = serde_json::to_vec_pretty(manifest)?;
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.manifest_key(&manifest.snapshot_id))
+            .body(json.into())
+            .send()
+            .await
+            .context("S3 put_object (manifest) failed")?;
+        Ok(())
+    }
+
+    async fn list_manifests(&self) -> Result<Vec<String>> {
+        let mut ids: Vec<String> = self
+            .list_all_keys("manifests/")
+            .await?
+            .iter()
+            .filter_map(|key| key.strip_prefix("manifests/"))
+            .filter_map(|name| name.strip_suffix(".json"))
+            .map(|id| id.to_string())
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    async fn get_manifest(&self, snapshot_id: &str) -> Result<SnapshotManifest> {
+        let resp = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.manifest_key(snapshot_id))
+            .send()
+            .await
+            .context("S3 get_object (manifest) failed")?;
+        let bytes = resp.body.collect().await.context("Reading S3 manifest body")?;
+        Ok(serde_json::from_slice(&bytes.into_bytes())?)
+    }
+
+    async fn delete_manifest(&self, snapshot_id: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.manifest_key(snapshot_id))
+            .send()
+            .await
+            .context("S3 delete_object (manifest) failed")?;
+        Ok(())
+    }
+}
+
+/// Parse a `--store-url` like `file:///path/to/store` or
+/// `s3://bucket?endpoint=...&region=...` into an `ObjectStore`.
+pub async fn open_store(url: &str) -> Result<Box<dyn ObjectStore>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Ok(Box::new(LocalStore::new(PathBuf::from(path))));
+    }
+    if let Some(rest) = url.strip_prefix("s3://") {
+        let (bucket, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut endpoint = None;
+        let mut region = "us-east-1".to_string();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            if let Some((key, value)) = pair.split_once('=') {
+                match key {
+                    "endpoint" => endpoint = Some(value.to_string()),
+                    "region" => region = value.to_string(),
+                    _ => {}
+                }
+            }
+        }
+        let store = S3Store::new(endpoint.as_deref(), bucket, &region).await?;
+        return Ok(Box::new(store));
+    }
+    anyhow::bail!("Unsupported store URL scheme: {} (expected file:// or s3://)", url)
+}